@@ -1,16 +1,42 @@
 package edu.kaist.salab;
 
 import org.objectweb.asm.ClassVisitor;
+import org.objectweb.asm.Label;
 import org.objectweb.asm.MethodVisitor;
 import org.objectweb.asm.Opcodes;
 
 public class DependencyVisitor extends ClassVisitor {
     private String classname;
+    private String file;
 
     private static String getModuleName(String classname, String methodName, String methodDescriptor) {
         return classname + "." + methodName + methodDescriptor;
     }
 
+    private static String getPackageName(String classname) {
+        int index = classname.lastIndexOf('/');
+        return index < 0 ? "" : classname.substring(0, index);
+    }
+
+    private static String getKind(int opcode) {
+        switch (opcode) {
+            case Opcodes.INVOKESTATIC:
+                return "static";
+            case Opcodes.INVOKEVIRTUAL:
+                return "virtual";
+            case Opcodes.INVOKEINTERFACE:
+                return "interface";
+            case Opcodes.INVOKESPECIAL:
+                return "special";
+            default:
+                return "other";
+        }
+    }
+
+    private static String quote(String s) {
+        return "\"" + s.replace("\\", "\\\\").replace("\"", "\\\"") + "\"";
+    }
+
     public DependencyVisitor() {
         super(Opcodes.ASM9);
     }
@@ -18,25 +44,60 @@ public class DependencyVisitor extends ClassVisitor {
     @Override
     public void visit(int version, int access, String name, String signature, String superName, String[] interfaces) {
         classname = name;
+        file = null;
+    }
+
+    @Override
+    public void visitSource(String source, String debug) {
+        // The source file is in the directory of the package, e.g., "edu/kaist/salab/App.java".
+        if (source != null) {
+            String packageName = getPackageName(classname);
+            file = packageName.isEmpty() ? source : packageName + "/" + source;
+        }
     }
 
     @Override
     public MethodVisitor visitMethod(final int access, final String name, final String descriptor, final String signature, final String[] exceptions) {
-        return new MethodDependencyVisitor(getModuleName(this.classname, name, descriptor));
+        return new MethodDependencyVisitor(getModuleName(this.classname, name, descriptor), getPackageName(this.classname), this.file);
     }
 
     static class MethodDependencyVisitor extends MethodVisitor {
         private final String caller;
+        private final String callerModule;
+        private final String file;
+        private int line;
 
-        public MethodDependencyVisitor(String caller) {
+        public MethodDependencyVisitor(String caller, String callerModule, String file) {
             super(Opcodes.ASM9);
             this.caller = caller;
+            this.callerModule = callerModule;
+            this.file = file;
+        }
+
+        @Override
+        public void visitLineNumber(final int line, final Label start) {
+            this.line = line;
         }
 
         @Override
         public void visitMethodInsn(final int opcode, final String owner, final String name, final String descriptor, boolean isInterface) {
             String callee = getModuleName(owner, name, descriptor);
-            System.out.println("{\"caller\":\"" + this.caller + "\",\"callee\":\"" + callee + "\"}");
+
+            StringBuilder dr = new StringBuilder();
+            dr.append("{\"caller\":").append(quote(this.caller));
+            dr.append(",\"callee\":").append(quote(callee));
+            if (this.file != null) {
+                dr.append(",\"file\":").append(quote(this.file));
+            }
+            if (this.line > 0) {
+                dr.append(",\"line\":").append(this.line);
+            }
+            dr.append(",\"lang\":\"java\"");
+            dr.append(",\"kind\":").append(quote(getKind(opcode)));
+            dr.append(",\"callerModule\":").append(quote(this.callerModule));
+            dr.append(",\"calleeModule\":").append(quote(getPackageName(owner)));
+            dr.append("}");
+            System.out.println(dr);
         }
     }
 }
@@ -2,6 +2,7 @@ package lib
 
 import (
 	"fmt"
+	"path/filepath"
 	"strings"
 
 	"golang.org/x/tools/go/callgraph"
@@ -19,6 +20,11 @@ func ReadDependencies(mainPkgName string, dir string) error {
 	prog, _ := ssautil.AllPackages(pkgs, 0)
 	prog.Build()
 
+	root, err := filepath.Abs(dir)
+	if err != nil {
+		return err
+	}
+
 	cg := static.CallGraph(prog)
 	cg.DeleteSyntheticNodes()
 
@@ -34,10 +40,32 @@ func ReadDependencies(mainPkgName string, dir string) error {
 			return nil
 		}
 
+		// A called method is named with its receiver, e.g., "(*net/http.Client).Do", as in the
+		// connector catalog of sarex
+		callee := fmt.Sprintf("%s.%s", calleePkg, calleeFunc)
+		if edge.Callee.Func.Signature.Recv() != nil {
+			callee = edge.Callee.Func.String()
+		}
+
 		dr := DependencyRelation{
-			Caller: fmt.Sprintf("%s.%s", callerPkg, callerFunc),
-			Callee: fmt.Sprintf("%s.%s", calleePkg, calleeFunc),
+			Caller:       fmt.Sprintf("%s.%s", callerPkg, callerFunc),
+			Callee:       callee,
+			Lang:         "go",
+			Kind:         "static",
+			CallerModule: callerPkg,
+			CalleeModule: calleePkg,
+		}
+
+		// The position of the call site, with a path relative to the root
+		if pos := prog.Fset.Position(edge.Pos()); pos.IsValid() {
+			dr.File = pos.Filename
+			if rel, err := filepath.Rel(root, pos.Filename); err == nil {
+				dr.File = rel
+			}
+			dr.Line = pos.Line
+			dr.Offset = pos.Offset
 		}
+
 		if err := dr.Print(); err != nil {
 			return err
 		}
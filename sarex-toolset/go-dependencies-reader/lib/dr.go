@@ -7,8 +7,15 @@ import (
 )
 
 type DependencyRelation struct {
-	Caller string `json:"caller"`
-	Callee string `json:"callee"`
+	Caller       string `json:"caller"`
+	Callee       string `json:"callee"`
+	File         string `json:"file,omitempty"`
+	Line         int    `json:"line,omitempty"`
+	Offset       int    `json:"offset,omitempty"`
+	Lang         string `json:"lang,omitempty"`
+	Kind         string `json:"kind,omitempty"`
+	CallerModule string `json:"callerModule,omitempty"`
+	CalleeModule string `json:"calleeModule,omitempty"`
 }
 
 func (dr DependencyRelation) Print() error {
@@ -29,6 +29,10 @@ async function run() {
     .map(({ caller, callee }) => ({
       caller: path.resolve(caller.source),
       callee: resolveCallee(callee),
+      file: path.relative(rootPath, caller.source),
+      lang: "js",
+      kind: callee.dynamic ? "dynamic_import" : "import",
+      calleeModule: callee.module,
     }))
     .forEach((item) => console.log(JSON.stringify(item)));
 }
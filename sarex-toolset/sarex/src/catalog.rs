@@ -0,0 +1,239 @@
+use std::{collections::BTreeSet, error::Error, fs::OpenOptions, io::BufReader};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::model::{
+    drs::Dr,
+    mapping_rules::{MappingRule, Relation},
+};
+
+const CATALOG_FILE: &str = "catalog.json";
+
+/// A well-known library call that is a candidate connector. `target` is a prefix of the targets
+/// of dependency relations, which ends at a boundary of a name, e.g., "os.Open" matches
+/// "os.Open" but not "os.OpenFile".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub lang: String,
+    pub target: String,
+
+    #[serde(rename = "connectorType")]
+    pub connector_type: String,
+
+    #[serde(rename = "sourceComponentIdentifierSchema")]
+    pub source_component_identifier_schema: Vec<String>,
+
+    #[serde(rename = "targetComponentIdentifierSchema")]
+    pub target_component_identifier_schema: Vec<String>,
+}
+
+impl CatalogEntry {
+    pub fn is_match(&self, target: &str) -> bool {
+        match target.strip_prefix(&self.target) {
+            Some(rest) => !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'),
+            None => false,
+        }
+    }
+}
+
+/// Language, connector type, target identifier schema, and targets of built-in entries. Their
+/// source components are identified by "process_id".
+const BUILTIN_ENTRIES: &[(&str, &str, &[&str], &[&str])] = &[
+    (
+        "java",
+        "File",
+        &["file_path"],
+        &[
+            "java/io/File.<init>",
+            "java/io/FileInputStream.<init>",
+            "java/io/FileOutputStream.<init>",
+            "java/io/FileReader.<init>",
+            "java/io/FileWriter.<init>",
+            "java/io/RandomAccessFile.<init>",
+            "java/nio/file/Files.newInputStream",
+            "java/nio/file/Files.newOutputStream",
+            "java/nio/file/Files.readAllBytes",
+            "java/nio/file/Files.readAllLines",
+            "java/nio/file/Files.write",
+        ],
+    ),
+    (
+        "java",
+        "Network",
+        &["registry_name"],
+        &[
+            "java/rmi/registry/Registry.lookup",
+            "java/rmi/registry/Registry.bind",
+            "java/rmi/registry/Registry.rebind",
+            "java/rmi/Naming.lookup",
+        ],
+    ),
+    (
+        "java",
+        "Network",
+        &["host", "port"],
+        &["java/net/Socket.<init>"],
+    ),
+    (
+        "java",
+        "Network",
+        &["port"],
+        &["java/net/ServerSocket.<init>"],
+    ),
+    (
+        "java",
+        "HTTP",
+        &["url"],
+        &[
+            "java/net/URL.openConnection",
+            "java/net/http/HttpClient.send",
+        ],
+    ),
+    (
+        "java",
+        "DB",
+        &["url"],
+        &["java/sql/DriverManager.getConnection"],
+    ),
+    (
+        "java",
+        "IPC",
+        &["command"],
+        &["java/lang/Runtime.exec", "java/lang/ProcessBuilder.start"],
+    ),
+    (
+        "go",
+        "File",
+        &["file_path"],
+        &[
+            "os.Open",
+            "os.OpenFile",
+            "os.Create",
+            "os.ReadFile",
+            "os.WriteFile",
+        ],
+    ),
+    (
+        "go",
+        "Network",
+        &["address"],
+        &["net.Dial", "net.DialTimeout", "net.Listen"],
+    ),
+    (
+        "go",
+        "HTTP",
+        &["url"],
+        &["net/http.Get", "net/http.Post", "(*net/http.Client).Do"],
+    ),
+    (
+        "go",
+        "DB",
+        &["url"],
+        &[
+            "database/sql.Open",
+            "go.mongodb.org/mongo-driver/mongo.Connect",
+        ],
+    ),
+    ("go", "IPC", &["command"], &["os/exec.Command"]),
+    ("js", "File", &["file_path"], &["fs"]),
+    ("js", "Network", &["host", "port"], &["net"]),
+    (
+        "js",
+        "HTTP",
+        &["url"],
+        &["http", "https", "node-fetch", "axios"],
+    ),
+    ("js", "DB", &["url"], &["mongodb"]),
+    ("js", "IPC", &["command"], &["child_process"]),
+];
+
+/// Reads the catalog, which is the entries of `$HOME/.sarex/catalog.json`, if any, followed by
+/// the built-in entries. The first entry that matches a dependency relation is used, so an entry
+/// of the file can replace a built-in one.
+pub fn read_catalog() -> Result<Vec<CatalogEntry>, Box<dyn Error>> {
+    let mut catalog: Vec<CatalogEntry> = Vec::new();
+
+    let p = config::get_sarex_dir().join(CATALOG_FILE);
+    if p.exists() {
+        let file = OpenOptions::new().read(true).open(p)?;
+        catalog = serde_json::from_reader(BufReader::new(file))?;
+    }
+
+    for (lang, connector_type, target_schema, targets) in BUILTIN_ENTRIES {
+        for target in *targets {
+            catalog.push(CatalogEntry {
+                lang: lang.to_string(),
+                target: target.to_string(),
+                connector_type: connector_type.to_string(),
+                source_component_identifier_schema: vec!["process_id".to_string()],
+                target_component_identifier_schema: target_schema
+                    .iter()
+                    .map(|identifier| identifier.to_string())
+                    .collect(),
+            });
+        }
+    }
+
+    Ok(catalog)
+}
+
+/// The languages that dependency relations record.
+pub fn get_langs(drs: &[Dr]) -> BTreeSet<&str> {
+    drs.iter()
+        .filter_map(|dr| dr.details.lang.as_deref())
+        .collect()
+}
+
+/// Proposes a mapping rule for each dependency relation whose target is in the catalog for its
+/// language, unless the dependency relation already has a mapping rule. `default_lang` is the
+/// language of dependency relations without one, which are skipped if it is not given. Returns
+/// the proposed mapping rules and the skipped dependency relations.
+pub fn suggest<'a>(
+    drs: &'a [Dr],
+    existing_mapping_rules: &[MappingRule],
+    catalog: &[CatalogEntry],
+    project_id: &str,
+    default_lang: Option<&str>,
+) -> (Vec<MappingRule>, Vec<&'a Dr>) {
+    let mut mapping_rules: Vec<MappingRule> = Vec::new();
+    let mut skipped: Vec<&Dr> = Vec::new();
+
+    for dr in drs {
+        let Some(lang) = dr.details.lang.as_deref().or(default_lang) else {
+            skipped.push(dr);
+            continue;
+        };
+
+        let has_mapping_rule = existing_mapping_rules
+            .iter()
+            .any(|r| r.relation.source == dr.source && r.relation.target == dr.target);
+        if has_mapping_rule {
+            continue;
+        }
+
+        let Some(entry) = catalog
+            .iter()
+            .find(|entry| entry.lang == lang && entry.is_match(&dr.target))
+        else {
+            continue;
+        };
+
+        mapping_rules.push(MappingRule {
+            id: None,
+            project_id: project_id.to_string(),
+            procedure: dr.source.clone(),
+            relation: Relation {
+                id: dr.id.map(|id| id.to_hex()).unwrap_or_default(),
+                source: dr.source.clone(),
+                target: dr.target.clone(),
+                project_id: project_id.to_string(),
+            },
+            connector_type: entry.connector_type.clone(),
+            source_component_identifier_schema: entry.source_component_identifier_schema.clone(),
+            target_component_identifier_schema: entry.target_component_identifier_schema.clone(),
+        });
+    }
+
+    (mapping_rules, skipped)
+}
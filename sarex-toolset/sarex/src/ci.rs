@@ -3,10 +3,11 @@ use std::{
     error::Error,
     fmt::{self, Display, Formatter},
     fs::OpenOptions,
-    io::{BufRead, BufReader, Write},
+    io::{self, BufRead, BufReader, BufWriter, Write},
     path::Path,
 };
 
+use log::{log, Level};
 use serde::{Deserialize, Serialize};
 
 use crate::model::mapping_rules::MappingRule;
@@ -22,20 +23,34 @@ pub struct ExecutionTrace {
     pub target_values: HashMap<String, String>,
 }
 
-pub fn read_execution_traces(file_path_str: String) -> Result<Vec<ExecutionTrace>, Box<dyn Error>> {
+const MAX_SAMPLES: usize = 5;
+
+/// An execution trace, or the reason why its line cannot be parsed, with its line number.
+pub type TraceLine = (usize, Result<ExecutionTrace, String>);
+
+/// Reads execution traces line by line, so that the whole log does not need to fit in memory. An
+/// error of reading the file is given as an item, so that the caller can stop there.
+pub fn read_execution_traces(
+    file_path_str: String,
+) -> Result<impl Iterator<Item = io::Result<TraceLine>>, Box<dyn Error>> {
     let file_path = Path::new(&file_path_str);
     let execution_traces_file = OpenOptions::new().read(true).open(file_path)?;
     let reader = BufReader::new(execution_traces_file);
-    let mut execution_traces: Vec<ExecutionTrace> = Vec::new();
-    for line in reader.lines().flatten() {
-        let execution_trace: ExecutionTrace = match serde_json::from_str(&line) {
-            Ok(execution_trace) => execution_trace,
-            Err(_) => {
-                continue;
-            }
-        };
-        execution_traces.push(execution_trace);
-    }
+    let execution_traces = reader
+        .split(b'\n')
+        .enumerate()
+        .filter(|(_, line)| match line {
+            Ok(line) => !line.trim_ascii().is_empty(),
+            Err(_) => true,
+        })
+        .map(|(i, line)| {
+            let line = line?;
+            let execution_trace = String::from_utf8(line)
+                .map_err(|e| e.to_string())
+                .and_then(|line| serde_json::from_str(&line).map_err(|e| e.to_string()));
+
+            Ok((i + 1, execution_trace))
+        });
 
     Ok(execution_traces)
 }
@@ -45,55 +60,167 @@ pub struct Ci {
     pub id: String,
     pub connector_type: String,
     pub source_component_values: HashMap<String, String>,
-    // pub additional_source_component_values: HashMap<String, String>,
+
+    /// Source values outside the source schema, which describe the execution context, but do not
+    /// identify the source component.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub additional_source_component_values: HashMap<String, String>,
+
     pub target_component_values: HashMap<String, String>,
 }
 
-pub fn create_cis(
-    execution_traces: Vec<ExecutionTrace>,
-    mapping_rules: Vec<MappingRule>,
-) -> Result<Vec<Ci>, Box<dyn Error>> {
-    let mut cis: Vec<Ci> = Vec::new();
+/// Execution traces that are not converted to connector instances, or converted with empty target
+/// values.
+#[derive(Debug, Default)]
+pub struct TraceReport {
+    pub malformed: TraceIssue,
+    pub unmatched: TraceIssue,
+    pub empty_target_values: TraceIssue,
+}
 
-    for execution_trace in execution_traces {
-        let mapping_rule = find_corresponding_mapping_rule(&mapping_rules, &execution_trace.id)?;
+#[derive(Debug, Default)]
+pub struct TraceIssue {
+    pub count: usize,
+    pub samples: Vec<String>,
+}
 
-        // 모든 Source Value 들을 기록해두어야, 나중에 찾을 수 있음 (execution context)
-        let mut source_component_values: HashMap<String, String> = HashMap::new();
-        for (key, value) in execution_trace.source_values {
-            if !value.is_empty() {
-                source_component_values.insert(key, value);
-            }
+impl TraceIssue {
+    fn add(&mut self, line_number: usize, message: String) {
+        self.add_sample(format!("line {}: {}", line_number, message));
+    }
+
+    /// Counts an issue, and keeps its description as a sample if there are not enough samples.
+    pub fn add_sample(&mut self, sample: String) {
+        self.count += 1;
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(sample);
         }
+    }
+}
+
+impl TraceReport {
+    pub fn is_empty(&self) -> bool {
+        self.malformed.count == 0
+            && self.unmatched.count == 0
+            && self.empty_target_values.count == 0
+    }
 
-        let mut target_component_values: HashMap<String, String> = HashMap::new();
-        for identifier in mapping_rule.target_component_identifier_schema {
-            let value = execution_trace.target_values.get(&identifier);
-            if let Some(value) = value {
-                if !value.is_empty() {
-                    target_component_values.insert(identifier, value.clone());
-                }
+    /// Logs each category as an error if `strict` is set, or as a warning otherwise.
+    pub fn log(&self, strict: bool) {
+        let level = if strict { Level::Error } else { Level::Warn };
+        for (title, issue) in [
+            ("malformed lines", &self.malformed),
+            (
+                "traces without a mapping rule of the project",
+                &self.unmatched,
+            ),
+            ("traces with empty target values", &self.empty_target_values),
+        ] {
+            if issue.count == 0 {
+                continue;
+            }
+
+            log!(level, "{} {}", issue.count, title);
+            for sample in &issue.samples {
+                log!(level, "    {}", sample);
             }
         }
+    }
+}
 
-        let ci = Ci {
-            id: execution_trace.id.clone(),
-            connector_type: mapping_rule.connector_type.clone(),
-            source_component_values,
-            // additional_source_component_values,
-            target_component_values,
+/// Converts execution traces to connector instances. Traces that cannot be converted are skipped
+/// and recorded in `report`, but errors of reading them are passed on. Traces with empty target
+/// values are recorded too, but only skipped if `strict` is set.
+pub fn create_cis<'a>(
+    execution_traces: impl Iterator<Item = io::Result<TraceLine>> + 'a,
+    mapping_rules: &'a [MappingRule],
+    report: &'a mut TraceReport,
+    project_sources: bool,
+    strict: bool,
+) -> impl Iterator<Item = io::Result<Ci>> + 'a {
+    execution_traces.filter_map(move |execution_trace| {
+        let (line_number, execution_trace) = match execution_trace {
+            Ok(execution_trace) => execution_trace,
+            Err(e) => return Some(Err(e)),
         };
+        let execution_trace = match execution_trace {
+            Ok(execution_trace) => execution_trace,
+            Err(e) => {
+                report.malformed.add(line_number, e);
+                return None;
+            }
+        };
+
+        let mapping_rule = match find_corresponding_mapping_rule(mapping_rules, &execution_trace.id)
+        {
+            Ok(mapping_rule) => mapping_rule,
+            Err(e) => {
+                report
+                    .unmatched
+                    .add(line_number, format!("{} ({})", e, execution_trace.id));
+                return None;
+            }
+        };
+
+        let ci = create_ci(execution_trace, mapping_rule, project_sources);
+        if ci.target_component_values.is_empty() {
+            report.empty_target_values.add(line_number, ci.id.clone());
+            if strict {
+                return None;
+            }
+        }
+
+        Some(Ok(ci))
+    })
+}
 
-        cis.push(ci);
+/// Creates a CI from an execution trace. All non-empty source values are kept as the execution
+/// context, unless `project_sources` is set. Then, only the values of the source schema identify
+/// the source component, and the others are kept as additional values. A mapping rule without a
+/// source schema keeps all of them.
+pub fn create_ci(
+    execution_trace: ExecutionTrace,
+    mapping_rule: &MappingRule,
+    project_sources: bool,
+) -> Ci {
+    let schema = &mapping_rule.source_component_identifier_schema;
+    let mut source_component_values: HashMap<String, String> = HashMap::new();
+    let mut additional_source_component_values: HashMap<String, String> = HashMap::new();
+    for (key, value) in execution_trace.source_values {
+        if value.is_empty() {
+            continue;
+        }
+
+        if project_sources && !schema.is_empty() && !schema.contains(&key) {
+            additional_source_component_values.insert(key, value);
+        } else {
+            source_component_values.insert(key, value);
+        }
     }
 
-    Ok(cis)
+    let mut target_component_values: HashMap<String, String> = HashMap::new();
+    for identifier in &mapping_rule.target_component_identifier_schema {
+        let value = execution_trace.target_values.get(identifier);
+        if let Some(value) = value {
+            if !value.is_empty() {
+                target_component_values.insert(identifier.clone(), value.clone());
+            }
+        }
+    }
+
+    Ci {
+        id: execution_trace.id,
+        connector_type: mapping_rule.connector_type.clone(),
+        source_component_values,
+        additional_source_component_values,
+        target_component_values,
+    }
 }
 
-fn find_corresponding_mapping_rule(
-    mapping_rules: &[MappingRule],
+fn find_corresponding_mapping_rule<'a>(
+    mapping_rules: &'a [MappingRule],
     execution_trace_id: &str,
-) -> Result<MappingRule, CIError> {
+) -> Result<&'a MappingRule, CIError> {
     let mapping_rule_id = get_mapping_rule_id(execution_trace_id)?;
     let mapping_rule = mapping_rules
         .iter()
@@ -106,7 +233,7 @@ fn find_corresponding_mapping_rule(
         })
         .ok_or(CIError::NoCorrespondingMappingRule)?;
 
-    Ok(mapping_rule.clone())
+    Ok(mapping_rule)
 }
 
 fn get_mapping_rule_id(execution_trace_id: &str) -> Result<String, CIError> {
@@ -118,17 +245,51 @@ fn get_mapping_rule_id(execution_trace_id: &str) -> Result<String, CIError> {
     Ok(split[0].to_string())
 }
 
-pub fn write_cis(cis: Vec<Ci>, output_file_path_str: &str) -> Result<(), Box<dyn Error>> {
+/// Splits an execution trace ID into its mapping rule ID, procedure, and index. A procedure may
+/// contain '_', so the index is taken from the last part.
+pub fn split_execution_trace_id(execution_trace_id: &str) -> (&str, &str, Option<u64>) {
+    let Some((mapping_rule_id, rest)) = execution_trace_id.split_once('_') else {
+        return (execution_trace_id, "", None);
+    };
+
+    match rest.rsplit_once('_') {
+        Some((procedure, index)) => match index.parse() {
+            Ok(index) => (mapping_rule_id, procedure, Some(index)),
+            Err(_) => (mapping_rule_id, rest, None),
+        },
+        None => (mapping_rule_id, rest, None),
+    }
+}
+
+/// Writes connector instances as JSON lines while they are created, and returns the number of them.
+pub fn write_cis(
+    cis: impl Iterator<Item = io::Result<Ci>>,
+    output_file_path_str: &str,
+) -> Result<usize, Box<dyn Error>> {
     let p = Path::new(output_file_path_str);
-    let mut file = OpenOptions::new().write(true).create(true).open(p)?;
-    file.write_all(serde_json::to_string_pretty(&cis)?.as_bytes())?;
-    Ok(())
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(p)?;
+    let mut writer = BufWriter::new(file);
+
+    let mut count = 0;
+    for ci in cis {
+        serde_json::to_writer(&mut writer, &ci?)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+
+    Ok(count)
 }
 
 #[derive(Debug)]
-enum CIError {
+pub enum CIError {
     MalformedExecutionTraceId,
     NoCorrespondingMappingRule,
+    InvalidExecutionTraces,
 }
 
 impl Error for CIError {}
@@ -138,6 +299,12 @@ impl Display for CIError {
         match *self {
             CIError::MalformedExecutionTraceId => write!(f, "Malformed execution trace id"),
             CIError::NoCorrespondingMappingRule => write!(f, "No corresponding mapping rule"),
+            CIError::InvalidExecutionTraces => {
+                write!(
+                    f,
+                    "Some execution traces are not converted to connector instances"
+                )
+            }
         }
     }
 }
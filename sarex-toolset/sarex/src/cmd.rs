@@ -1,9 +1,10 @@
 use super::{config, model::*};
-use crate::{ci, conn, model, plugin};
+use crate::model::store::Store;
+use crate::{catalog, ci, conn, diff, dr, eval, group, instrument, matching, model, plugin, rule};
 use clap::{Parser, Subcommand};
-use log::{error, info};
+use log::{error, info, warn};
 
-use std::{error::Error, fmt::Display};
+use std::{collections::HashMap, error::Error, fmt::Display};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -21,7 +22,13 @@ enum Commands {
         db_url: String,
     },
 
-    /// Get the database URL, which is currently set, and projects, which are stored in the database
+    /// Use a local directory instead of MongoDB to store projects, dependency relations, and mapping rules
+    SetDir {
+        /// The data directory, which is created if it does not exist and stored as an absolute path. If the directory is not provided, $HOME/.sarex/data is used.
+        data_dir: Option<String>,
+    },
+
+    /// Get the database URL or the data directory, which is currently set, and projects, which are stored in it
     GetDB {},
 
     /// Set the project ID
@@ -35,18 +42,51 @@ enum Commands {
     },
 
     /// Filter dependency relations from source code to external libraries
+    #[command(subcommand_negates_reqs = true)]
     Dr {
-        #[arg(short, long)]
+        #[command(subcommand)]
+        command: Option<DrCommands>,
+
+        #[arg(short, long, required = true)]
         /// A root path for the target software
-        root_path: String,
+        root_path: Option<String>,
+
+        #[arg(short, long, required = true)]
+        /// A programming language of the target software. "java", "go", and "js" are built in. Other languages are supported by plugins installed in $HOME/.sarex/plugins/<name> with a plugin.json manifest.
+        lang: Option<String>,
+
+        #[arg(short, long)]
+        /// Packages or directories of the target software. Comma separated values are allowed. They are internal patterns unless --internal or the source filter of the current project is given.
+        sources: Option<String>,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Patterns of internal code, whose calls to external code are extracted. Overrides the source filter of the current project.
+        internal: Vec<String>,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Patterns of external code, whose callers are extracted. Overrides the source filter of the current project.
+        external: Vec<String>,
+
+        #[arg(long)]
+        /// Remove stored dependency relations of the current project with the same language and sources that are not extracted anymore, and duplicates of stored ones. The others are kept with their IDs, so that mapping rules stay linked to them.
+        prune: bool,
+    },
 
+    /// Manage mapping rules of the current project
+    Rule {
+        #[command(subcommand)]
+        command: RuleCommands,
+    },
+
+    /// Generate probes that record execution traces for mapping rules of the current project
+    Instrument {
         #[arg(short, long)]
-        /// A programming language of the target software. Currently, only "java", "go", and "js" are supported.
+        /// A programming language of the target software. Currently, "java", "go", and "js" are supported.
         lang: String,
 
         #[arg(short, long)]
-        /// Packages or directories of the target software. Comma separated values are allowed.
-        sources: String,
+        /// An output directory for the probes
+        output_dir: String,
     },
 
     /// Extract connector instances from execution traces
@@ -58,6 +98,18 @@ enum Commands {
         #[arg(short, long)]
         /// An output file path that contains connector instances
         output_file: String,
+
+        #[arg(long, conflicts_with = "lenient")]
+        /// Fail if any execution trace is malformed, has no mapping rule, or has empty target values
+        strict: bool,
+
+        #[arg(long)]
+        /// Only warn about execution traces that are not converted to connector instances, and keep those with empty target values (default)
+        lenient: bool,
+
+        #[arg(long)]
+        /// Identify source components only by the source schema of each mapping rule. Other source values are kept as additional_source_component_values.
+        project_sources: bool,
     },
 
     /// Build an execution view model from connector instances
@@ -70,18 +122,298 @@ enum Commands {
         /// An output file path that contains an execution view model
         output_file: String,
 
+        #[arg(short, long, value_parser = ["json", "png", "dot", "mermaid", "plantuml", "structurizr", "graphml", "gexf", "cytoscape", "html"])]
+        /// An output format of the execution view model
+        format: String,
+
+        #[arg(long, default_value = "hash", value_parser = ["hash", "slug"])]
+        /// How component IDs are derived from their identifier values. "hash" gives a short hash, and "slug" gives readable IDs such as "process_id=10012".
+        id_style: String,
+
+        #[arg(long)]
+        /// Draw components with the same value of an identifier (e.g., "host") in a cluster, or with the same directory of a path value (e.g., "file_path:dir"). Repeat it to nest clusters.
+        cluster_by: Vec<String>,
+
+        #[arg(long)]
+        /// Merge components with the same value of an identifier (e.g., "host"), or with the same directory of a path value (e.g., "file_path:dir"), into one component. Connectors between them are aggregated with their counts.
+        collapse: Vec<String>,
+
+        #[arg(long)]
+        /// Scale the width of each edge by the number of its connector instances in "png", "dot", and "html"
+        scale_edges: bool,
+
+        #[arg(long, conflicts_with = "no_policies")]
+        /// A file path that contains a JSON array of identity policies, which are used instead of those of the current project. The database is not accessed.
+        policies: Option<String>,
+
+        #[arg(long)]
+        /// Match components without identity policies, by their values only. The database is not accessed.
+        no_policies: bool,
+    },
+
+    /// Evaluate an execution view model against a ground-truth model with precision, recall, and F1
+    Eval {
         #[arg(short, long)]
-        /// An output format of the execution view model. Currently, "json", "png", and "dot" are supported.
+        /// A file path of the reconstructed execution view model in JSON
+        model: String,
+
+        #[arg(short, long)]
+        /// A file path of the ground-truth execution view model in JSON
+        truth: String,
+
+        #[arg(short, long, default_value = "text", value_parser = ["text", "json"])]
+        /// An output format of the evaluation
         format: String,
     },
+
+    /// Explain why a component or a connector exists, from its CIs to their mapping rules
+    Explain {
+        /// The ID of a component or a connector in the execution view model
+        element_id: String,
+
+        #[arg(short, long)]
+        /// A file path of the execution view model in JSON
+        model: String,
+    },
+
+    /// Manage identity policies of the current project, which decide when component values are the same component
+    Identity {
+        #[command(subcommand)]
+        command: IdentityCommands,
+    },
+
+    /// Work with execution view models
+    Model {
+        #[command(subcommand)]
+        command: ModelCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelCommands {
+    /// Compare two execution view models in JSON
+    Diff {
+        /// A file path of the old execution view model
+        a: String,
+
+        /// A file path of the new execution view model
+        b: String,
+
+        #[arg(short, long, default_value = "text", value_parser = ["text", "json", "dot"])]
+        /// An output format of the differences
+        format: String,
+
+        #[arg(short, long)]
+        /// An output file path. If not given, the differences are printed to stdout.
+        output_file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DrCommands {
+    /// List dependency relations of the current project by their targets, which are external APIs, with their callers
+    List {
+        #[arg(short, long)]
+        /// Only list dependency relations whose targets start with the prefix
+        target: Option<String>,
+
+        #[arg(long)]
+        /// Only list dependency relations whose targets match the regular expression
+        target_regex: Option<String>,
+
+        #[arg(short, long)]
+        /// Only list dependency relations whose sources start with the package or directory
+        source: Option<String>,
+
+        #[arg(long, conflicts_with = "without_rules")]
+        /// Only list dependency relations that have mapping rules
+        with_rules: bool,
+
+        #[arg(long)]
+        /// Only list dependency relations that have no mapping rules
+        without_rules: bool,
+
+        #[arg(long)]
+        /// Group external APIs by their external libraries, and only show the number of their callers
+        by_library: bool,
+    },
+
+    /// Show or set the patterns of internal and external code of the current project, which are used to extract dependency relations
+    Filter {
+        #[arg(long, value_delimiter = ',')]
+        /// Patterns of internal code, e.g., "edu.kaist.*,!edu.kaist.test". A pattern is a glob, or a regular expression after "re:", and a pattern after "!" excludes code.
+        internal: Vec<String>,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Patterns of external code. If not given, any code that is not internal is external.
+        external: Vec<String>,
+
+        #[arg(long, conflicts_with_all = ["internal", "external"])]
+        /// Remove the source filter
+        clear: bool,
+    },
+
+    /// Show a dependency relation with its call site
+    Show {
+        /// The dependency relation ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IdentityCommands {
+    /// List identity policies of the current project in the order they are tried
+    List {},
+
+    /// Replace identity policies of the current project with those in a JSON file
+    Import {
+        /// A file path that contains a JSON array of identity policies
+        file: String,
+    },
+
+    /// Export identity policies of the current project to a JSON file
+    Export {
+        /// An output file path
+        file: String,
+    },
+
+    /// Remove all identity policies of the current project
+    Clear {},
+}
+
+#[derive(Subcommand)]
+enum RuleCommands {
+    /// List mapping rules of the current project
+    List {},
+
+    /// Show a mapping rule
+    Show {
+        /// The mapping rule ID
+        id: String,
+    },
+
+    /// Add a new mapping rule for a dependency relation, which is stored by the dr command
+    Add {
+        #[arg(short, long)]
+        /// The ID of the dependency relation. If the ID is not provided, the dependency relation is found by --target and --source.
+        dr: Option<String>,
+
+        #[arg(short, long)]
+        /// The target (callee) of the dependency relation
+        target: Option<String>,
+
+        #[arg(long)]
+        /// The source (caller) of the dependency relation. It is needed only when several dependency relations have the same target.
+        source: Option<String>,
+
+        #[arg(short, long)]
+        /// The procedure of the mapping rule. If the procedure is not provided, the source of the dependency relation is used.
+        procedure: Option<String>,
+
+        #[arg(short, long)]
+        /// The connector type, e.g., "File" or "Network"
+        connector_type: String,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Identifiers of the source component. Comma separated values are allowed.
+        source_schema: Vec<String>,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Identifiers of the target component. Comma separated values are allowed.
+        target_schema: Vec<String>,
+    },
+
+    /// Edit a mapping rule. Only the provided fields are changed.
+    Edit {
+        /// The mapping rule ID
+        id: String,
+
+        #[arg(short, long)]
+        /// The procedure of the mapping rule
+        procedure: Option<String>,
+
+        #[arg(short, long)]
+        /// The connector type
+        connector_type: Option<String>,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Identifiers of the source component. Comma separated values are allowed.
+        source_schema: Option<Vec<String>>,
+
+        #[arg(long, value_delimiter = ',')]
+        /// Identifiers of the target component. Comma separated values are allowed.
+        target_schema: Option<Vec<String>>,
+    },
+
+    /// Remove a mapping rule
+    Rm {
+        /// The mapping rule ID
+        id: String,
+    },
+
+    /// Export mapping rules of the current project to a JSON file
+    Export {
+        /// An output file path
+        file: String,
+    },
+
+    /// Import mapping rules from a JSON file into the current project
+    Import {
+        /// A file path that contains mapping rules, which is written by the export command
+        file: String,
+
+        #[arg(long)]
+        /// Assign new IDs to the imported mapping rules
+        new_ids: bool,
+    },
+
+    /// Check mapping rules for mistakes, and exit with a non-zero status if there is an error
+    Lint {
+        #[arg(short, long)]
+        /// A file path that contains mapping rules, which is written by the export command. If the file path is not provided, mapping rules of the current project are checked.
+        file: Option<String>,
+    },
+
+    /// Propose mapping rules for dependency relations of the current project whose targets are in the connector catalog
+    Suggest {
+        #[arg(short, long)]
+        /// An output file path for the proposed mapping rules, which can be reviewed and imported by the import command. If the file path is not provided, they are listed.
+        output_file: Option<String>,
+
+        #[arg(short, long)]
+        /// The programming language of dependency relations that do not record one, e.g., "java", "go", or "js". If the language is not provided, the language of the other dependency relations is used if they all record the same one, and they are skipped otherwise.
+        lang: Option<String>,
+    },
+
+    /// List the connector catalog, which is $HOME/.sarex/catalog.json followed by the built-in entries
+    Catalog {
+        #[arg(short, long)]
+        /// Only list the entries of a programming language, e.g., "java", "go", or "js"
+        lang: Option<String>,
+    },
+
+    /// Show or set the connector types that mapping rules of the current project may have
+    Types {
+        #[arg(value_delimiter = ',')]
+        /// The connector types, e.g., "File,Network,Registry". If the connector types are not provided, the current ones are shown.
+        connector_types: Vec<String>,
+
+        #[arg(long, conflicts_with = "connector_types")]
+        /// Allow any connector type
+        clear: bool,
+    },
 }
 
 #[derive(Debug)]
 enum CmdError {
     NotEnoughArguments,
     NoSuchProject,
-    WrongArguments,
     NoProjectIdSet,
+    NoSuchDr,
+    AmbiguousDr(usize),
+    NoSuchMappingRule,
+    NoSuchElement,
+    NoInternalPatterns,
 }
 
 impl Error for CmdError {}
@@ -91,8 +423,19 @@ impl Display for CmdError {
         match self {
             CmdError::NotEnoughArguments => write!(f, "Not enough arguments"),
             CmdError::NoSuchProject => write!(f, "No such project"),
-            CmdError::WrongArguments => write!(f, "Wrong arguments"),
             CmdError::NoProjectIdSet => write!(f, "No project ID is set"),
+            CmdError::NoSuchDr => write!(f, "No such dependency relation"),
+            CmdError::AmbiguousDr(count) => write!(
+                f,
+                "{} dependency relations match. Specify --source or --dr",
+                count
+            ),
+            CmdError::NoSuchMappingRule => write!(f, "No such mapping rule"),
+            CmdError::NoSuchElement => write!(f, "No such component or connector in the model"),
+            CmdError::NoInternalPatterns => write!(
+                f,
+                "No internal code is given. Specify --sources or --internal, or set a source filter with 'sarex dr filter'"
+            ),
         }
     }
 }
@@ -102,29 +445,79 @@ pub async fn init_app() {
 
     match run_command(cli.command).await {
         Ok(_) => {}
-        Err(e) => error!("{}", e),
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
     }
 }
 
 async fn run_command(cmd: Option<Commands>) -> Result<(), Box<dyn Error>> {
     match cmd {
         Some(Commands::SetDB { db_url }) => set_db(db_url).await,
+        Some(Commands::SetDir { data_dir }) => set_dir(data_dir),
         Some(Commands::GetDB {}) => get_db().await,
         Some(Commands::SetProject { project_id, name }) => set_project(project_id, name).await,
         Some(Commands::Dr {
+            command: Some(command),
+            ..
+        }) => run_dr_command(command).await,
+        Some(Commands::Dr {
+            command: None,
             root_path,
             lang,
             sources,
-        }) => save_drs(root_path, lang, sources).await,
+            internal,
+            external,
+            prune,
+        }) => match (root_path, lang) {
+            (Some(root_path), Some(lang)) => {
+                let source_filter = projects::SourceFilter { internal, external };
+                save_drs(root_path, lang, sources, source_filter, prune).await
+            }
+            _ => Err(Box::new(CmdError::NotEnoughArguments)),
+        },
+        Some(Commands::Rule { command }) => run_rule_command(command).await,
+        Some(Commands::Instrument { lang, output_dir }) => write_probes(lang, output_dir).await,
         Some(Commands::Ci {
             execution_traces,
             output_file,
-        }) => extract_cis(execution_traces, output_file).await,
+            strict,
+            lenient: _,
+            project_sources,
+        }) => extract_cis(execution_traces, output_file, strict, project_sources).await,
         Some(Commands::Conn {
             ci_file,
             output_file,
             format,
-        }) => build_connectors(ci_file, output_file, format),
+            id_style,
+            cluster_by,
+            collapse,
+            scale_edges,
+            policies,
+            no_policies,
+        }) => {
+            build_connectors(
+                ci_file,
+                output_file,
+                format,
+                id_style,
+                cluster_by,
+                collapse,
+                scale_edges,
+                policies,
+                no_policies,
+            )
+            .await
+        }
+        Some(Commands::Eval {
+            model,
+            truth,
+            format,
+        }) => evaluate_model(model, truth, format),
+        Some(Commands::Explain { element_id, model }) => explain_element(element_id, model).await,
+        Some(Commands::Identity { command }) => run_identity_command(command).await,
+        Some(Commands::Model { command }) => run_model_command(command),
         None => {
             error!("No command provided");
             Ok(())
@@ -138,6 +531,28 @@ async fn set_db(db_url: String) -> Result<(), Box<dyn Error>> {
     let mut config = config::read()?;
 
     config.db_url = db_url;
+    config.storage = config::Storage::Mongo;
+
+    config::write(&config)?;
+
+    Ok(())
+}
+
+fn set_dir(data_dir: Option<String>) -> Result<(), Box<dyn Error>> {
+    let mut config = config::read()?;
+
+    // A relative path is resolved now, so that it does not depend on the working directory later.
+    let data_dir = match data_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)?;
+            let dir = std::fs::canonicalize(dir)?;
+            Some(dir.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+
+    config.storage = config::Storage::File;
+    config.data_dir = data_dir;
 
     config::write(&config)?;
 
@@ -146,13 +561,14 @@ async fn set_db(db_url: String) -> Result<(), Box<dyn Error>> {
 
 async fn get_db() -> Result<(), Box<dyn Error>> {
     let config = config::read()?;
+    let store = Store::from_config(&config);
 
     let mut s = String::new();
 
-    if !config.db_url.is_empty() {
-        s.push_str(&format!("db_url: {}\n", config.db_url));
-    } else {
-        s.push_str("db_url: <NOT SET>\n");
+    match &store {
+        Store::Mongo(url) if !url.is_empty() => s.push_str(&format!("db_url: {}\n", url)),
+        Store::Mongo(_) => s.push_str("db_url: <NOT SET>\n"),
+        Store::File(dir) => s.push_str(&format!("data_dir: {}\n", dir.display())),
     }
 
     let project_id = match config.project_id {
@@ -162,8 +578,8 @@ async fn get_db() -> Result<(), Box<dyn Error>> {
 
     s.push_str(&format!("project_id: {}\n", &project_id));
 
-    if !config.db_url.is_empty() {
-        let projects = projects::read_many(&config.db_url).await?;
+    if store.is_configured() {
+        let projects = projects::read_many(&store).await?;
 
         s.push_str("projects:\n");
         for project in projects {
@@ -193,14 +609,15 @@ async fn set_project(
     name: Option<String>,
 ) -> Result<(), Box<dyn Error>> {
     let mut config = config::read()?;
+    let store = Store::from_config(&config);
 
     let id = match (project_id, name) {
-        (Some(id), Some(name)) => projects::update(&config.db_url, &id, name).await?,
-        (Some(id), None) => match projects::read_one(&config.db_url, &id).await? {
+        (Some(id), Some(name)) => projects::update(&store, &id, name).await?,
+        (Some(id), None) => match projects::read_one(&store, &id).await? {
             Some(_) => id,
             None => return Err(Box::new(CmdError::NoSuchProject)),
         },
-        (None, Some(name)) => projects::create(&config.db_url, name).await?,
+        (None, Some(name)) => projects::create(&store, name).await?,
         (None, None) => {
             return Err(Box::new(CmdError::NotEnoughArguments));
         }
@@ -213,8 +630,15 @@ async fn set_project(
     Ok(())
 }
 
-async fn save_drs(root_path: String, lang: String, sources: String) -> Result<(), Box<dyn Error>> {
+async fn save_drs(
+    root_path: String,
+    lang: String,
+    sources: Option<String>,
+    source_filter: projects::SourceFilter,
+    prune: bool,
+) -> Result<(), Box<dyn Error>> {
     let config = config::read()?;
+    let store = Store::from_config(&config);
     let project_id = match config.project_id {
         Some(id) => id,
         None => {
@@ -222,65 +646,910 @@ async fn save_drs(root_path: String, lang: String, sources: String) -> Result<()
         }
     };
 
-    let kind: plugin::PluginKind = match lang.as_str() {
-        "java" => plugin::PluginKind::Java,
-        "go" => plugin::PluginKind::Go,
-        "js" => plugin::PluginKind::JavaScript,
-        _ => return Err(Box::new(CmdError::WrongArguments)),
+    // Patterns of the options come first, then those of the project, and then the sources.
+    let project_filter = projects::read_one(&store, &project_id)
+        .await?
+        .map(|project| project.source_filter)
+        .unwrap_or_default();
+    let source_patterns = sources
+        .as_deref()
+        .map(|sources| sources.split(',').map(String::from).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let mut source_filter = merge_source_filters(source_filter, project_filter);
+    if source_filter.internal.is_empty() {
+        source_filter.internal = source_patterns.clone();
+    }
+    if source_filter.internal.is_empty() {
+        return Err(Box::new(CmdError::NoInternalPatterns));
+    }
+    let classifier = dr::SourceClassifier::new(&source_filter)?;
+    let in_sources = dr::Patterns::new(&source_patterns)?;
+
+    let extractor = plugin::get_extractor(&lang)?;
+
+    let sources = sources.unwrap_or_default();
+    let params = plugin::DrParams {
+        root_path: &root_path,
+        sources: &sources,
     };
 
-    let params: Vec<&str> = vec![&root_path, &sources];
+    let all_drs = extractor.read_drs(&project_id, &params)?;
 
-    let all_drs = plugin::read_drs(&project_id, kind, params)?;
-    if all_drs.is_empty() {
-        info!("No drs found");
-        return Ok(());
+    let filtered_drs = all_drs
+        .into_iter()
+        .filter(|dr| classifier.is_dependency(dr))
+        .map(|mut dr| {
+            dr.details.lang.get_or_insert_with(|| lang.clone());
+            dr
+        })
+        .collect::<Vec<_>>();
+
+    // Dependency relations that are stored already keep their IDs, so that mapping rules stay linked.
+    // Only those of the same language, source filter, and sources can disappear, so that extracting
+    // one part of a project does not remove the others.
+    let existing = drs::read_many(&store, &project_id).await?;
+    let changes = dr::get_changes(filtered_drs, &existing, |dr| {
+        dr.details.lang.as_deref() == Some(lang.as_str())
+            && classifier.is_dependency(dr)
+            && in_sources.is_match(&dr.source)
+    });
+    if !changes.added.is_empty() {
+        drs::create_many(&store, changes.added.iter().collect()).await?;
     }
+    drs::update_many(&store, &changes.updated).await?;
 
-    let s = sources.split(',').collect::<Vec<_>>();
+    for dr in &changes.disappeared {
+        info!("Disappeared: {} -> {}", dr.source, dr.target);
+    }
+    info!(
+        "Added {} dependency relations, kept {} ({} with new call sites), and {} disappeared since the last extraction",
+        changes.added.len(),
+        changes.kept,
+        changes.updated.len(),
+        changes.disappeared.len()
+    );
 
-    let filtered_drs = all_drs
-        .iter()
-        .filter(|dr| !is_start_with(&dr.target, &s) && is_start_with(&dr.source, &s))
+    if prune {
+        let ids = changes
+            .disappeared
+            .iter()
+            .chain(changes.duplicates.iter())
+            .filter_map(|dr| dr.id)
+            .collect::<Vec<_>>();
+        let count = drs::delete_many(&store, &ids).await?;
+        info!(
+            "Removed {} dependency relations that disappeared or were duplicated",
+            count
+        );
+    } else if !changes.disappeared.is_empty() {
+        info!("Run with --prune to remove the dependency relations that disappeared");
+    }
+
+    for mapping_rule in mapping_rules::read_many(&store, &project_id).await? {
+        let has_disappeared = changes.disappeared.iter().any(|dr| {
+            dr.source == mapping_rule.relation.source && dr.target == mapping_rule.relation.target
+        });
+        if has_disappeared {
+            warn!(
+                "Mapping rule {} maps a dependency relation that disappeared: {} -> {}",
+                mapping_rule.id.map(|id| id.to_hex()).unwrap_or_default(),
+                mapping_rule.relation.source,
+                mapping_rule.relation.target
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Takes each list of patterns from `a` if it is given, or from `b` otherwise.
+fn merge_source_filters(
+    a: projects::SourceFilter,
+    b: projects::SourceFilter,
+) -> projects::SourceFilter {
+    projects::SourceFilter {
+        internal: if a.internal.is_empty() {
+            b.internal
+        } else {
+            a.internal
+        },
+        external: if a.external.is_empty() {
+            b.external
+        } else {
+            a.external
+        },
+    }
+}
+
+async fn run_dr_command(cmd: DrCommands) -> Result<(), Box<dyn Error>> {
+    let config = config::read()?;
+    let store = Store::from_config(&config);
+    let project_id = config.project_id.ok_or(CmdError::NoProjectIdSet)?;
+
+    match cmd {
+        DrCommands::List {
+            target,
+            target_regex,
+            source,
+            with_rules,
+            without_rules,
+            by_library,
+        } => {
+            let filter = dr::DrFilter {
+                target_prefix: target,
+                target_regex: target_regex.map(|r| regex::Regex::new(&r)).transpose()?,
+                source_prefix: source,
+                has_rules: match (with_rules, without_rules) {
+                    (true, _) => Some(true),
+                    (_, true) => Some(false),
+                    _ => None,
+                },
+            };
+
+            let all_drs = drs::read_many(&store, &project_id).await?;
+            let mapping_rules = mapping_rules::read_many(&store, &project_id).await?;
+            let apis = dr::group_by_api(dr::filter_drs(&all_drs, &mapping_rules, &filter));
+
+            let mut s = String::new();
+            if by_library {
+                for (library, apis) in dr::group_by_library(apis) {
+                    let callers = apis.iter().map(|api| api.callers.len()).sum::<usize>();
+                    s.push_str(&format!(
+                        "{} ({} APIs, {} callers)\n",
+                        library,
+                        apis.len(),
+                        callers
+                    ));
+                    for api in apis {
+                        s.push_str(&format!(
+                            "    {} ({} callers)\n",
+                            api.target,
+                            api.callers.len()
+                        ));
+                    }
+                }
+            } else {
+                for api in apis {
+                    s.push_str(&format!("{} ({} callers)\n", api.target, api.callers.len()));
+                    for entry in api.callers {
+                        let id = entry.dr.id.map(|id| id.to_hex()).unwrap_or_default();
+                        let rules = match entry.rule_count {
+                            0 => String::new(),
+                            count => format!(" [{} mapping rules]", count),
+                        };
+                        s.push_str(&format!(
+                            "    {}: {}{}{}\n",
+                            id,
+                            entry.dr.source,
+                            get_call_site(&entry.dr.details),
+                            rules
+                        ));
+                    }
+                }
+            }
+
+            print!("{}", s);
+            Ok(())
+        }
+        DrCommands::Filter {
+            internal,
+            external,
+            clear,
+        } => {
+            let project = projects::read_one(&store, &project_id)
+                .await?
+                .ok_or(CmdError::NoSuchProject)?;
+
+            if clear {
+                return projects::set_source_filter(
+                    &store,
+                    &project_id,
+                    projects::SourceFilter::default(),
+                )
+                .await;
+            }
+            if !internal.is_empty() || !external.is_empty() {
+                // A list that is not given is kept.
+                let source_filter = merge_source_filters(
+                    projects::SourceFilter { internal, external },
+                    project.source_filter,
+                );
+                // Invalid patterns are not stored.
+                dr::SourceClassifier::new(&source_filter)?;
+
+                return projects::set_source_filter(&store, &project_id, source_filter).await;
+            }
+
+            if project.source_filter.is_empty() {
+                info!("No source filter is set. --sources are internal patterns");
+            }
+            println!("internal: {}", project.source_filter.internal.join(","));
+            println!("external: {}", project.source_filter.external.join(","));
+
+            Ok(())
+        }
+        DrCommands::Show { id } => {
+            let dr = read_dr(&store, &project_id, &id).await?;
+
+            println!("{}", serde_json::to_string_pretty(&dr)?);
+            Ok(())
+        }
+    }
+}
+
+/// The location and the kind of a call, e.g., " (src/App.java:12, virtual)", if any.
+fn get_call_site(details: &drs::DrDetails) -> String {
+    let location = match (&details.file, details.line, details.offset) {
+        (Some(file), Some(line), _) => Some(format!("{}:{}", file, line)),
+        (Some(file), None, Some(offset)) => Some(format!("{} @{}", file, offset)),
+        (Some(file), None, None) => Some(file.clone()),
+        (None, _, _) => None,
+    };
+
+    let parts = location
+        .into_iter()
+        .chain(details.kind.map(|kind| kind.to_string()))
         .collect::<Vec<_>>();
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
+    }
+}
 
-    match drs::create_many(&config.db_url, filtered_drs).await {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+async fn run_rule_command(cmd: RuleCommands) -> Result<(), Box<dyn Error>> {
+    let config = config::read()?;
+    let store = Store::from_config(&config);
+    let project_id = config.project_id.ok_or(CmdError::NoProjectIdSet)?;
+
+    match cmd {
+        RuleCommands::List {} => list_mapping_rules(&store, &project_id).await,
+        RuleCommands::Show { id } => show_mapping_rule(&store, &project_id, &id).await,
+        RuleCommands::Add {
+            dr,
+            target,
+            source,
+            procedure,
+            connector_type,
+            source_schema,
+            target_schema,
+        } => {
+            let dr = find_dr(&store, &project_id, dr, target, source).await?;
+            let relation = mapping_rules::Relation {
+                id: dr.id.map(|id| id.to_hex()).unwrap_or_default(),
+                source: dr.source.clone(),
+                target: dr.target,
+                project_id: dr.project_id,
+            };
+
+            let mapping_rule = mapping_rules::MappingRule {
+                id: None,
+                project_id,
+                procedure: procedure.unwrap_or(dr.source),
+                relation,
+                connector_type,
+                source_component_identifier_schema: source_schema,
+                target_component_identifier_schema: target_schema,
+            };
+
+            let id = mapping_rules::create(&store, mapping_rule).await?;
+            println!("{}", id);
+
+            Ok(())
+        }
+        RuleCommands::Edit {
+            id,
+            procedure,
+            connector_type,
+            source_schema,
+            target_schema,
+        } => {
+            let mut mapping_rule = read_mapping_rule(&store, &project_id, &id).await?;
+
+            if let Some(procedure) = procedure {
+                mapping_rule.procedure = procedure;
+            }
+            if let Some(connector_type) = connector_type {
+                mapping_rule.connector_type = connector_type;
+            }
+            if let Some(source_schema) = source_schema {
+                mapping_rule.source_component_identifier_schema = source_schema;
+            }
+            if let Some(target_schema) = target_schema {
+                mapping_rule.target_component_identifier_schema = target_schema;
+            }
+
+            mapping_rules::update(&store, &id, mapping_rule).await?;
+
+            Ok(())
+        }
+        RuleCommands::Rm { id } => {
+            read_mapping_rule(&store, &project_id, &id).await?;
+
+            match mapping_rules::delete(&store, &id).await? {
+                true => Ok(()),
+                false => Err(Box::new(CmdError::NoSuchMappingRule)),
+            }
+        }
+        RuleCommands::Export { file } => {
+            let mapping_rules = mapping_rules::read_many(&store, &project_id).await?;
+            rule::write_mapping_rules(&mapping_rules, &file)
+        }
+        RuleCommands::Import { file, new_ids } => {
+            import_mapping_rules(&store, &project_id, &file, new_ids).await
+        }
+        RuleCommands::Suggest { output_file, lang } => {
+            let drs = drs::read_many(&store, &project_id).await?;
+            let existing = mapping_rules::read_many(&store, &project_id).await?;
+            let catalog = catalog::read_catalog()?;
+
+            // Without --lang, dependency relations without a language have the language of the
+            // others, if they all have the same one.
+            let langs = catalog::get_langs(&drs);
+            let default_lang = match (lang.as_deref(), langs.len()) {
+                (Some(lang), _) => Some(lang),
+                (None, 1) => langs.first().copied(),
+                (None, _) => None,
+            };
+            let (suggested, skipped) =
+                catalog::suggest(&drs, &existing, &catalog, &project_id, default_lang);
+            if !skipped.is_empty() {
+                let reason = if langs.is_empty() {
+                    "no dependency relation of the project records one".to_string()
+                } else {
+                    format!(
+                        "the project has more than one: {}",
+                        langs.iter().copied().collect::<Vec<_>>().join(", ")
+                    )
+                };
+                for dr in &skipped {
+                    warn!(
+                        "Skipped {} -> {} without a language, since {}. Give it with --lang",
+                        dr.source, dr.target, reason
+                    );
+                }
+            }
+            match output_file {
+                Some(file) => {
+                    rule::write_mapping_rules(&suggested, &file)?;
+                    info!(
+                        "Wrote {} mapping rules. Review them and run `sarex rule import {}`",
+                        suggested.len(),
+                        file
+                    );
+                }
+                None => {
+                    let mut s = String::new();
+                    for mapping_rule in &suggested {
+                        s.push_str(&format!(
+                            "{}: {} -> {}\n    source: [{}], target: [{}]\n",
+                            mapping_rule.connector_type,
+                            mapping_rule.procedure,
+                            mapping_rule.relation.target,
+                            mapping_rule.source_component_identifier_schema.join(", "),
+                            mapping_rule.target_component_identifier_schema.join(", "),
+                        ));
+                    }
+                    print!("{}", s);
+                }
+            }
+
+            Ok(())
+        }
+        RuleCommands::Catalog { lang } => {
+            let mut s = String::new();
+            for entry in catalog::read_catalog()? {
+                if lang.as_ref().is_some_and(|lang| &entry.lang != lang) {
+                    continue;
+                }
+
+                s.push_str(&format!(
+                    "{} {}: {}, source: [{}], target: [{}]\n",
+                    entry.lang,
+                    entry.target,
+                    entry.connector_type,
+                    entry.source_component_identifier_schema.join(", "),
+                    entry.target_component_identifier_schema.join(", "),
+                ));
+            }
+            print!("{}", s);
+
+            Ok(())
+        }
+        RuleCommands::Lint { file } => lint_mapping_rules(&store, &project_id, file).await,
+        RuleCommands::Types {
+            connector_types,
+            clear,
+        } => {
+            if clear || !connector_types.is_empty() {
+                return projects::set_connector_types(&store, &project_id, connector_types).await;
+            }
+
+            let project = projects::read_one(&store, &project_id)
+                .await?
+                .ok_or(CmdError::NoSuchProject)?;
+            if project.connector_types.is_empty() {
+                info!("Any connector type is allowed");
+            }
+            for connector_type in project.connector_types {
+                println!("{}", connector_type);
+            }
+
+            Ok(())
+        }
     }
 }
 
-fn is_start_with(item: &str, sources: &Vec<&str>) -> bool {
-    for source in sources {
-        if item.starts_with(source) {
-            return true;
+async fn lint_mapping_rules(
+    store: &Store,
+    project_id: &str,
+    file: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let mapping_rules = match file {
+        Some(file) => rule::read_mapping_rules(&file)?,
+        None => mapping_rules::read_many(store, project_id).await?,
+    };
+    let drs = drs::read_many(store, project_id).await?;
+    let connector_types = projects::read_one(store, project_id)
+        .await?
+        .map(|project| project.connector_types)
+        .unwrap_or_default();
+
+    let issues = rule::lint(&mapping_rules, &drs, &connector_types);
+
+    let mut s = String::new();
+    for issue in &issues {
+        s.push_str(&format!(
+            "{}: {}: {}\n",
+            issue.severity, issue.mapping_rule_id, issue.message
+        ));
+    }
+    print!("{}", s);
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == rule::Severity::Error)
+        .count();
+    info!(
+        "Checked {} mapping rules: {} errors, {} warnings",
+        mapping_rules.len(),
+        errors,
+        issues.len() - errors
+    );
+
+    if errors > 0 {
+        return Err(Box::new(rule::RuleError::LintFailed(errors)));
+    }
+
+    Ok(())
+}
+
+async fn import_mapping_rules(
+    store: &Store,
+    project_id: &str,
+    file: &str,
+    new_ids: bool,
+) -> Result<(), Box<dyn Error>> {
+    let imported = rule::read_mapping_rules(file)?;
+    let existing = mapping_rules::read_many(store, project_id).await?;
+    let drs = drs::read_many(store, project_id).await?;
+
+    let import = rule::prepare_import(imported, &existing, &drs, project_id, new_ids);
+
+    for conflict in &import.conflicts {
+        warn!(
+            "Skipped a conflicting mapping rule: {} -> {} (existing: {})",
+            conflict.mapping_rule.procedure,
+            conflict.mapping_rule.relation.target,
+            conflict.existing_id
+        );
+    }
+
+    let mut count = 0;
+    for mut mapping_rule in import.new_mapping_rules {
+        // The ID may be taken by a mapping rule of another project in the same database.
+        if let Some(id) = mapping_rule.id {
+            if mapping_rules::read_one(store, &id.to_hex())
+                .await?
+                .is_some()
+            {
+                mapping_rule.id = Some(bson::oid::ObjectId::new());
+                info!(
+                    "Assigned a new ID to {}, which is used by another project",
+                    id.to_hex()
+                );
+            }
         }
+
+        mapping_rules::create(store, mapping_rule).await?;
+        count += 1;
     }
 
-    false
+    info!(
+        "Imported {} mapping rules, skipped {} conflicts",
+        count,
+        import.conflicts.len()
+    );
+    Ok(())
+}
+
+async fn list_mapping_rules(store: &Store, project_id: &str) -> Result<(), Box<dyn Error>> {
+    let mut mapping_rules = mapping_rules::read_many(store, project_id).await?;
+    mapping_rules.sort_by(|a, b| a.connector_type.cmp(&b.connector_type));
+
+    let mut s = String::new();
+    for mapping_rule in mapping_rules {
+        let id = match mapping_rule.id {
+            Some(id) => id.to_hex(),
+            None => "".to_string(),
+        };
+
+        s.push_str(&format!(
+            "{}: {}, {} -> {}\n    source: [{}], target: [{}]\n",
+            id,
+            mapping_rule.connector_type,
+            mapping_rule.procedure,
+            mapping_rule.relation.target,
+            mapping_rule.source_component_identifier_schema.join(", "),
+            mapping_rule.target_component_identifier_schema.join(", "),
+        ));
+    }
+
+    print!("{}", s);
+    Ok(())
+}
+
+async fn show_mapping_rule(
+    store: &Store,
+    project_id: &str,
+    id: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mapping_rule = read_mapping_rule(store, project_id, id).await?;
+
+    println!("{}", serde_json::to_string_pretty(&mapping_rule)?);
+    Ok(())
+}
+
+/// Reads a mapping rule of the project. Mapping rules of other projects are not found.
+async fn read_mapping_rule(
+    store: &Store,
+    project_id: &str,
+    id: &str,
+) -> Result<mapping_rules::MappingRule, Box<dyn Error>> {
+    match mapping_rules::read_one(store, id).await? {
+        Some(mapping_rule) if mapping_rule.project_id == project_id => Ok(mapping_rule),
+        _ => Err(Box::new(CmdError::NoSuchMappingRule)),
+    }
+}
+
+/// Reads a dependency relation of the project. Those of other projects are not found.
+async fn read_dr(store: &Store, project_id: &str, id: &str) -> Result<drs::Dr, Box<dyn Error>> {
+    match drs::read_one(store, id).await? {
+        Some(dr) if dr.project_id == project_id => Ok(dr),
+        _ => Err(Box::new(CmdError::NoSuchDr)),
+    }
+}
+
+async fn find_dr(
+    store: &Store,
+    project_id: &str,
+    dr_id: Option<String>,
+    target: Option<String>,
+    source: Option<String>,
+) -> Result<drs::Dr, Box<dyn Error>> {
+    if let Some(id) = dr_id {
+        return read_dr(store, project_id, &id).await;
+    }
+
+    let target = target.ok_or(CmdError::NotEnoughArguments)?;
+    let mut candidates = drs::read_many(store, project_id)
+        .await?
+        .into_iter()
+        .filter(|dr| dr.target == target)
+        .filter(|dr| match &source {
+            Some(source) => &dr.source == source,
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    match candidates.len() {
+        0 => Err(Box::new(CmdError::NoSuchDr)),
+        1 => Ok(candidates.remove(0)),
+        count => {
+            for dr in &candidates {
+                let id = dr.id.map(|id| id.to_hex()).unwrap_or_default();
+                info!("{}: {} -> {}", id, dr.source, dr.target);
+            }
+
+            Err(Box::new(CmdError::AmbiguousDr(count)))
+        }
+    }
+}
+
+async fn write_probes(lang: String, output_dir: String) -> Result<(), Box<dyn Error>> {
+    let config = config::read()?;
+    let store = Store::from_config(&config);
+    let project_id = config.project_id.ok_or(CmdError::NoProjectIdSet)?;
+
+    let mapping_rules = mapping_rules::read_many(&store, &project_id).await?;
+
+    for path in instrument::write_probes(&mapping_rules, &lang, &output_dir)? {
+        info!("Generated {}", path);
+    }
+
+    Ok(())
 }
 
 async fn extract_cis(
     execution_traces_file_path_str: String,
     output_file_path_str: String,
+    strict: bool,
+    project_sources: bool,
 ) -> Result<(), Box<dyn Error>> {
     let config = config::read()?;
+    let store = Store::from_config(&config);
     let project_id = config.project_id.ok_or(CmdError::NoProjectIdSet)?;
 
     let execution_traces = ci::read_execution_traces(execution_traces_file_path_str)?;
-    let mapping_rules = model::mapping_rules::read_many(&config.db_url, &project_id).await?;
+    let mapping_rules = model::mapping_rules::read_many(&store, &project_id).await?;
 
-    let cis = ci::create_cis(execution_traces, mapping_rules)?;
+    let mut report = ci::TraceReport::default();
+    let cis = ci::create_cis(
+        execution_traces,
+        &mapping_rules,
+        &mut report,
+        project_sources,
+        strict,
+    );
+    let count = ci::write_cis(cis, &output_file_path_str)?;
+    info!("Wrote {} connector instances", count);
 
-    ci::write_cis(cis, &output_file_path_str)
+    report.log(strict);
+    if strict && !report.is_empty() {
+        return Err(Box::new(ci::CIError::InvalidExecutionTraces));
+    }
+
+    Ok(())
 }
 
-fn build_connectors(
+async fn run_identity_command(cmd: IdentityCommands) -> Result<(), Box<dyn Error>> {
+    let config = config::read()?;
+    let store = Store::from_config(&config);
+    let project_id = config.project_id.ok_or(CmdError::NoProjectIdSet)?;
+
+    match cmd {
+        IdentityCommands::List {} => {
+            let policies = identity_policies::read_many(&store, &project_id).await?;
+
+            let mut s = String::new();
+            for (i, policy) in policies.iter().enumerate() {
+                s.push_str(&format!(
+                    "#{}: connector type: {}, identifier schema: {}\n",
+                    i + 1,
+                    policy.connector_type.as_deref().unwrap_or("*"),
+                    match &policy.identifier_schema {
+                        Some(schema) => format!("[{}]", schema.join(", ")),
+                        None => "*".to_string(),
+                    }
+                ));
+                s.push_str(&format!(
+                    "    required: [{}], optional: [{}], keys: {}\n",
+                    policy.required.join(", "),
+                    policy.optional.join(", "),
+                    policy
+                        .keys
+                        .iter()
+                        .map(|key| format!("[{}]", key.join(", ")))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ));
+            }
+
+            print!("{}", s);
+            Ok(())
+        }
+        IdentityCommands::Import { file } => {
+            let policies = matching::read_policies(&file)?;
+            let count = policies.len();
+            identity_policies::replace_all(&store, &project_id, policies).await?;
+            info!("Imported {} identity policies", count);
+
+            Ok(())
+        }
+        IdentityCommands::Export { file } => {
+            let policies = identity_policies::read_many(&store, &project_id).await?;
+            matching::write_policies(&policies, &file)
+        }
+        IdentityCommands::Clear {} => {
+            identity_policies::replace_all(&store, &project_id, Vec::new()).await
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn build_connectors(
     ci_file: String,
     output_file: String,
     output_format: String,
+    id_style: String,
+    cluster_by: Vec<String>,
+    collapse: Vec<String>,
+    scale_edges: bool,
+    policies_file: Option<String>,
+    no_policies: bool,
 ) -> Result<(), Box<dyn Error>> {
+    let id_style = match id_style.as_str() {
+        "slug" => conn::IdStyle::Slug,
+        _ => conn::IdStyle::Hash,
+    };
+
+    // Identity policies are read from the file or the current project, if any. Without them,
+    // reused identifiers such as process IDs are over-merged, so a failure to read them is an
+    // error rather than a fallback.
+    let policies = if no_policies {
+        Vec::new()
+    } else if let Some(file) = policies_file {
+        matching::read_policies(&file)?
+    } else {
+        let config = config::read()?;
+        let store = Store::from_config(&config);
+        match config.project_id {
+            Some(project_id) if store.is_configured() => {
+                identity_policies::read_many(&store, &project_id).await?
+            }
+            _ => Vec::new(),
+        }
+    };
+    if !policies.is_empty() {
+        info!(
+            "Matching components with {} identity policies",
+            policies.len()
+        );
+    }
+
     let cis = conn::read_cis(&ci_file)?;
-    let model = conn::build_model(cis)?;
-    conn::write_model(model, &output_file, &output_format)
+    let mut model = conn::build_model(cis, &id_style, &policies)?;
+
+    let collapse_rules = collapse
+        .iter()
+        .map(|rule| group::parse_rule(rule))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !collapse_rules.is_empty() {
+        model = group::collapse_model(model, &collapse_rules, &id_style);
+    }
+
+    let cluster_rules = cluster_by
+        .iter()
+        .map(|rule| group::parse_rule(rule))
+        .collect::<Result<Vec<_>, _>>()?;
+    let options = conn::RenderOptions {
+        clusters: group::get_clusters(&model, &cluster_rules),
+        scale_edges,
+    };
+
+    conn::write_model(model, &options, &output_file, &output_format)
+}
+
+fn evaluate_model(model: String, truth: String, format: String) -> Result<(), Box<dyn Error>> {
+    let model = conn::read_model(&model)?;
+    let truth = conn::read_model(&truth)?;
+
+    eval::print_evaluation(&eval::evaluate(&model, &truth)?, &format)
+}
+
+async fn explain_element(element_id: String, model: String) -> Result<(), Box<dyn Error>> {
+    let model = conn::read_model(&model)?;
+
+    let mut s = String::new();
+    let (ci_ids, ci_count, truncated) =
+        if let Some(component) = model.components.iter().find(|c| c.id == element_id) {
+            s.push_str(&format!(
+                "component {}: {}\n",
+                component.id,
+                conn::get_component_key(&component.component_values)
+            ));
+            for (identifier, values) in &component.additional_component_values {
+                s.push_str(&format!(
+                    "  context {}: {}\n",
+                    identifier,
+                    values.iter().cloned().collect::<Vec<_>>().join(", ")
+                ));
+            }
+            (
+                component.ci_ids.clone(),
+                component.ci_count,
+                component.ci_ids_truncated,
+            )
+        } else if let Some(connector) = model.connectors.iter().find(|c| c.id == element_id) {
+            let get_key = |id: &str| {
+                model
+                    .components
+                    .iter()
+                    .find(|c| c.id == id)
+                    .map(|c| conn::get_component_key(&c.component_values))
+                    .unwrap_or_else(|| id.to_string())
+            };
+            s.push_str(&format!(
+                "connector {}: {}, {} -> {}\n",
+                connector.id,
+                connector.connector_type,
+                get_key(&connector.source_component_id),
+                get_key(&connector.target_component_id)
+            ));
+            // The CIs of a connector are the execution traces behind it.
+            (
+                connector.execution_trace_ids.iter().cloned().collect(),
+                connector.count,
+                connector.execution_trace_ids_truncated,
+            )
+        } else {
+            return Err(Box::new(CmdError::NoSuchElement));
+        };
+
+    if ci_ids.is_empty() {
+        warn!("No CIs are recorded in the model. Build the model again with `sarex conn`");
+    } else if truncated {
+        s.push_str(&format!(
+            "  {} of {} CIs (the list of CIs is truncated)\n",
+            ci_ids.len(),
+            ci_count
+        ));
+    }
+
+    // Mapping rules are resolved from the current project, if any.
+    let mut rules: HashMap<String, mapping_rules::MappingRule> = HashMap::new();
+    let config = config::read()?;
+    let store = Store::from_config(&config);
+    match config.project_id {
+        Some(project_id) if store.is_configured() => {
+            for mapping_rule in mapping_rules::read_many(&store, &project_id).await? {
+                if let Some(id) = mapping_rule.id {
+                    rules.insert(id.to_hex(), mapping_rule);
+                }
+            }
+        }
+        _ => warn!("No project is set, so mapping rules are not resolved"),
+    }
+
+    for ci_id in &ci_ids {
+        let (mapping_rule_id, procedure, index) = ci::split_execution_trace_id(ci_id);
+        s.push_str(&format!("  CI {}\n", ci_id));
+        s.push_str(&format!(
+            "    execution trace: procedure {}, index {}\n",
+            procedure,
+            index.map(|i| i.to_string()).unwrap_or_default()
+        ));
+        match rules.get(mapping_rule_id) {
+            Some(mapping_rule) => s.push_str(&format!(
+                "    mapping rule {}: {}, {} -> {}\n",
+                mapping_rule_id,
+                mapping_rule.connector_type,
+                mapping_rule.relation.source,
+                mapping_rule.relation.target
+            )),
+            None => s.push_str(&format!(
+                "    mapping rule {}: not found in the current project\n",
+                mapping_rule_id
+            )),
+        }
+    }
+
+    print!("{}", s);
+    Ok(())
+}
+
+fn run_model_command(cmd: ModelCommands) -> Result<(), Box<dyn Error>> {
+    match cmd {
+        ModelCommands::Diff {
+            a,
+            b,
+            format,
+            output_file,
+        } => {
+            let a = conn::read_model(&a)?;
+            let b = conn::read_model(&b)?;
+            let diff = diff::diff_models(&a, &b)?;
+            diff::write_diff(&diff, output_file.as_deref(), &format)
+        }
+    }
 }
@@ -11,10 +11,25 @@ use std::{
 pub struct Config {
     pub db_url: String,
     pub project_id: Option<String>,
+
+    #[serde(default)]
+    pub storage: Storage,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+    #[default]
+    Mongo,
+    File,
 }
 
 const SAREX_DIR: &str = ".sarex";
 const CONFIG_FILE: &str = "config.json";
+const DATA_DIR: &str = "data";
 
 pub fn read() -> Result<Config, Box<dyn Error>> {
     let p = get_path()?;
@@ -31,19 +46,37 @@ pub fn read() -> Result<Config, Box<dyn Error>> {
 pub fn write(c: &Config) -> Result<(), Box<dyn Error>> {
     let p = get_path()?;
 
-    let mut file = OpenOptions::new().write(true).create(true).open(p)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(p)?;
     file.write_all(serde_json::to_string(&c)?.as_bytes())?;
 
     Ok(())
 }
 
-fn get_path() -> Result<PathBuf, Box<dyn Error>> {
+pub fn get_default_data_dir() -> PathBuf {
+    let mut p = get_sarex_dir();
+    p.push(DATA_DIR);
+
+    p
+}
+
+/// `$HOME/.sarex`, which has the config and other files of the user
+pub fn get_sarex_dir() -> PathBuf {
     let mut p = PathBuf::new();
     if let Some(home) = dirs::home_dir() {
         p.push(home);
         p.push(SAREX_DIR);
     }
 
+    p
+}
+
+fn get_path() -> Result<PathBuf, Box<dyn Error>> {
+    let mut p = get_sarex_dir();
+
     fs::create_dir_all(&p)?;
 
     p.push(CONFIG_FILE);
@@ -55,6 +88,8 @@ fn create_new_config() -> Result<Config, Box<dyn Error>> {
     let config = Config {
         db_url: String::from(""),
         project_id: None,
+        storage: Storage::Mongo,
+        data_dir: None,
     };
 
     write(&config)?;
@@ -67,6 +102,7 @@ fn read_existing_config(p: &PathBuf) -> Result<Config, Box<dyn Error>> {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(false)
         .open(p)?;
 
     let mut content = String::new();
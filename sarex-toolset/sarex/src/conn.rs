@@ -1,28 +1,89 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     error::Error,
     fs::OpenOptions,
-    io::{BufReader, Write},
-    iter,
+    io::{BufRead, BufReader, Write},
     path::Path,
 };
 
 use graphviz_rust::{cmd::CommandArg, dot_structures::*, printer::DotPrinter};
 use graphviz_rust::{cmd::Format, dot_generator::*, exec, printer::PrinterContext};
-use rand::Rng;
+use log::warn;
 use serde::{Deserialize, Serialize};
 
-use crate::ci::Ci;
+use crate::ci::{self, Ci};
+use crate::export;
+use crate::group::Cluster;
+use crate::matching::ComponentMatcher;
+use crate::model::identity_policies::IdentityPolicy;
 
+/// The most execution trace IDs that a connector keeps.
+const MAX_TRACE_IDS: usize = 100;
+
+/// The most CI IDs that a component keeps.
+pub const MAX_CI_IDS: usize = 100;
+
+/// The most additional values of an identifier that a component keeps.
+pub const MAX_ADDITIONAL_VALUES: usize = 20;
+
+/// Reads connector instances written as JSON lines by `sarex ci`, or as a JSON array by older versions.
 pub fn read_cis(file_path_str: &str) -> Result<Vec<Ci>, Box<dyn Error>> {
     let file_path = Path::new(file_path_str);
     let cis_file = OpenOptions::new().read(true).open(file_path)?;
-    let reader = BufReader::new(cis_file);
-    let cis: Vec<Ci> = serde_json::from_reader(reader)?;
+    let mut reader = BufReader::new(cis_file);
+
+    let mut skipped_lines = 0;
+    if is_json_array(&mut reader, &mut skipped_lines)? {
+        let cis: Vec<Ci> = serde_json::from_reader(reader)?;
+        return Ok(cis);
+    }
+
+    let mut cis: Vec<Ci> = Vec::new();
+    for (i, line) in (skipped_lines..).zip(reader.lines()) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(&line) {
+            Ok(ci) => cis.push(ci),
+            Err(e) => warn!("Skipped line {} of {}: {}", i + 1, file_path_str, e),
+        }
+    }
 
     Ok(cis)
 }
 
+/// Skips leading whitespace, counting the lines it skips, and checks whether the rest is an array.
+fn is_json_array(
+    reader: &mut impl BufRead,
+    skipped_lines: &mut usize,
+) -> Result<bool, Box<dyn Error>> {
+    loop {
+        let buf = reader.fill_buf()?;
+        let (is_array, len) = match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => (Some(buf[i] == b'['), i),
+            None if buf.is_empty() => return Ok(false),
+            None => (None, buf.len()),
+        };
+
+        *skipped_lines += buf[..len].iter().filter(|b| **b == b'\n').count();
+        reader.consume(len);
+        if let Some(is_array) = is_array {
+            return Ok(is_array);
+        }
+    }
+}
+
+pub fn read_model(file_path_str: &str) -> Result<Model, Box<dyn Error>> {
+    let file_path = Path::new(file_path_str);
+    let model_file = OpenOptions::new().read(true).open(file_path)?;
+    let reader = BufReader::new(model_file);
+    let model: Model = serde_json::from_reader(reader)?;
+
+    Ok(model)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Model {
     pub connectors: Vec<Connector>,
@@ -36,160 +97,382 @@ impl Model {
             components: Vec::new(),
         }
     }
-
-    fn has_connector(&self, connector: &Connector) -> bool {
-        for c in &self.connectors {
-            if c.connector_type == connector.connector_type
-                && c.source_component_id == connector.source_component_id
-                && c.target_component_id == connector.target_component_id
-            {
-                return true;
-            }
-        }
-
-        false
-    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Connector {
+    #[serde(default)]
+    pub id: String,
+
     pub connector_type: String,
     pub source_component_id: String,
     pub target_component_id: String,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Component {
-    pub id: String,
-    pub component_values: HashMap<String, String>,
-}
+    /// The number of CIs that support this connector.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub count: usize,
 
-pub fn build_model(cis: Vec<Ci>) -> Result<Model, Box<dyn Error>> {
-    let mut model = Model::new();
+    /// The distinct IDs of the execution traces behind this connector, up to `MAX_TRACE_IDS`. They
+    /// are also the IDs of its CIs.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub execution_trace_ids: BTreeSet<String>,
 
-    for ci in &cis {
-        // Create source components first.
-        find_or_create_component_if_not_exist(&mut model, ci, true);
-    }
+    /// Whether `execution_trace_ids` leaves out some of the execution traces, whose number is
+    /// `count`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub execution_trace_ids_truncated: bool,
 
-    for ci in cis {
-        let source_component_id = find_or_create_component_if_not_exist(&mut model, &ci, true);
-        let target_component_id = find_or_create_component_if_not_exist(&mut model, &ci, false);
+    /// The procedures of the execution traces behind this connector.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub procedures: BTreeSet<String>,
 
-        create_connector_if_not_exist(
-            &mut model,
-            ci.connector_type,
+    /// The positions of the first and last CIs behind this connector in the CI file, which tell
+    /// when the connector was observed first and last.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_index: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_index: Option<u64>,
+}
+
+impl Connector {
+    pub fn new(
+        connector_type: String,
+        source_component_id: String,
+        target_component_id: String,
+    ) -> Self {
+        Self {
+            id: String::new(),
+            connector_type,
             source_component_id,
             target_component_id,
+            count: 0,
+            execution_trace_ids: BTreeSet::new(),
+            execution_trace_ids_truncated: false,
+            procedures: BTreeSet::new(),
+            first_index: None,
+            last_index: None,
+        }
+    }
+
+    /// Adds the statistics of another connector, which is the same connector or is aggregated
+    /// into this one.
+    pub fn merge(&mut self, other: &Connector) {
+        self.count += other.count.max(1);
+        let left_out = add_bounded(
+            &mut self.execution_trace_ids,
+            &other.execution_trace_ids,
+            MAX_TRACE_IDS,
         );
+        self.execution_trace_ids_truncated |= left_out || other.execution_trace_ids_truncated;
+        self.procedures.extend(other.procedures.iter().cloned());
+        self.first_index = match (self.first_index, other.first_index) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.last_index = match (self.last_index, other.last_index) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
     }
+}
 
-    Ok(model)
+/// Adds values to a set until it has `max` values, so that a model does not grow with the number
+/// of CIs. Returns whether a value is left out.
+pub fn add_bounded(set: &mut BTreeSet<String>, values: &BTreeSet<String>, max: usize) -> bool {
+    for value in values {
+        if set.len() >= max && !set.contains(value) {
+            return true;
+        }
+        set.insert(value.clone());
+    }
+
+    false
 }
 
-fn find_or_create_component_if_not_exist(model: &mut Model, ci: &Ci, is_source: bool) -> String {
-    let component_values = if is_source {
-        &ci.source_component_values
-    } else {
-        &ci.target_component_values
-    };
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
 
-    let component_id = find_component_in_model(model, component_values);
-    if let Some(component) = component_id {
-        component
-    } else {
-        let new_component_id = get_random_id(10);
-        let new_component_values = component_values.clone();
-        // let mut new_component_values = component_values.clone();
-        // if is_source {
-        //     new_component_values.extend(ci.additional_source_component_values.clone());
-        // }
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Component {
+    pub id: String,
+    pub component_values: BTreeMap<String, String>,
+
+    /// The IDs of the CIs whose source or target is this component, up to `MAX_CI_IDS`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ci_ids: Vec<String>,
+
+    /// The number of the CIs whose source or target is this component.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub ci_count: usize,
+
+    /// Whether `ci_ids` leaves out some of the CIs, whose number is `ci_count`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ci_ids_truncated: bool,
+
+    /// The additional source values of CIs whose source is this component, which do not identify it,
+    /// up to `MAX_ADDITIONAL_VALUES` per identifier.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub additional_component_values: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Component {
+    pub fn add_additional_values<'a>(
+        &mut self,
+        values: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) {
+        for (identifier, value) in values {
+            let set = self
+                .additional_component_values
+                .entry(identifier.clone())
+                .or_default();
+            if set.len() < MAX_ADDITIONAL_VALUES {
+                set.insert(value.clone());
+            }
+        }
+    }
+
+    pub fn add_ci_id(&mut self, ci_id: &str) {
+        self.ci_count += 1;
+        if self.ci_ids.len() < MAX_CI_IDS {
+            self.ci_ids.push(ci_id.to_string());
+        } else {
+            self.ci_ids_truncated = true;
+        }
+    }
+}
+
+/// How the ID of a component is derived from its values.
+pub enum IdStyle {
+    /// A hash of the values, e.g., "3f2a5c1d9e8b7a60"
+    Hash,
+
+    /// The values themselves, e.g., "process_id=10012"
+    Slug,
+}
 
+pub fn build_model(
+    cis: Vec<Ci>,
+    id_style: &IdStyle,
+    policies: &[IdentityPolicy],
+) -> Result<Model, Box<dyn Error>> {
+    let matcher = ComponentMatcher::new(&cis, policies);
+    matcher.log_ambiguities();
+
+    let mut model = Model::new();
+    for (i, component_values) in matcher.get_components().iter().enumerate() {
+        // A temporary ID, which is replaced after all components are merged.
         model.components.push(Component {
-            id: new_component_id.clone(),
-            component_values: new_component_values,
+            id: i.to_string(),
+            component_values: component_values.clone(),
+            ci_ids: Vec::new(),
+            ci_count: 0,
+            ci_ids_truncated: false,
+            additional_component_values: BTreeMap::new(),
         });
+    }
+
+    let mut connector_indexes: HashMap<(String, usize, usize), usize> = HashMap::new();
+    let mut unplaced = ci::TraceIssue::default();
+    for (position, ci) in cis.into_iter().enumerate() {
+        let (Some(source), Some(target)) = (
+            matcher.find(&ci.source_component_values),
+            matcher.find(&ci.target_component_values),
+        ) else {
+            unplaced.add_sample(format!("CI {} at position {}", ci.id, position));
+            continue;
+        };
 
-        new_component_id
+        // The source and the target of a CI may be the same component.
+        model.components[source].add_ci_id(&ci.id);
+        model.components[source].add_additional_values(&ci.additional_source_component_values);
+        if target != source {
+            model.components[target].add_ci_id(&ci.id);
+        }
+
+        let new_connector = get_connector(
+            &ci,
+            position,
+            model.components[source].id.clone(),
+            model.components[target].id.clone(),
+        );
+        let key = (ci.connector_type, source, target);
+        match connector_indexes.get(&key) {
+            Some(i) => model.connectors[*i].merge(&new_connector),
+            None => {
+                connector_indexes.insert(key, model.connectors.len());
+                model.connectors.push(new_connector);
+            }
+        }
     }
+
+    if unplaced.count > 0 {
+        warn!(
+            "{} CIs are left out, since their values do not match a component",
+            unplaced.count
+        );
+        for sample in &unplaced.samples {
+            warn!("    {}", sample);
+        }
+    }
+
+    assign_ids(&mut model, id_style);
+
+    Ok(model)
 }
 
-fn find_component_in_model(
-    model: &mut Model,
-    component_values: &HashMap<String, String>,
-) -> Option<String> {
+/// Replaces the temporary IDs of components with IDs derived from their values, and assigns IDs to
+/// connectors derived from their types and components, so that the same connector instances
+/// always result in the same model.
+pub fn assign_ids(model: &mut Model, id_style: &IdStyle) {
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut used_ids: HashSet<String> = HashSet::new();
+
     for component in &mut model.components {
-        let is_component_large = component.component_values.len() > component_values.len();
-        let (large_values, small_values) = if is_component_large {
-            (&component.component_values, component_values)
-        } else {
-            (component_values, &component.component_values)
-        };
+        let base_id = get_component_id(&component.component_values, id_style);
 
-        let mut is_same = true;
-        for (identifier, value) in small_values {
-            if !value.is_empty() {
-                let component_value: Option<&String> = large_values.get(identifier);
-                is_same = is_same && component_value == Some(value);
-            }
+        let mut id = base_id.clone();
+        let mut n = 2;
+        while !used_ids.insert(id.clone()) {
+            id = format!("{}-{}", base_id, n);
+            n += 1;
         }
 
-        if is_same {
-            if !is_component_large {
-                for (identifier, value) in component_values {
-                    if !value.is_empty() {
-                        let component_value: Option<&String> =
-                            component.component_values.get(identifier);
-                        if component_value.is_none() {
-                            component
-                                .component_values
-                                .insert(identifier.clone(), value.clone());
-                        }
-                    }
-                }
-            }
+        ids.insert(component.id.clone(), id.clone());
+        component.id = id;
+    }
+
+    for connector in &mut model.connectors {
+        if let Some(id) = ids.get(&connector.source_component_id) {
+            connector.source_component_id = id.clone();
+        }
+        if let Some(id) = ids.get(&connector.target_component_id) {
+            connector.target_component_id = id.clone();
+        }
+
+        let base_id = get_connector_id(connector, id_style);
 
-            return Some(component.id.clone());
+        let mut id = base_id.clone();
+        let mut n = 2;
+        while !used_ids.insert(id.clone()) {
+            id = format!("{}-{}", base_id, n);
+            n += 1;
         }
+        connector.id = id;
     }
+}
+
+fn get_component_id(component_values: &BTreeMap<String, String>, id_style: &IdStyle) -> String {
+    let key = get_component_key(component_values);
 
-    None
+    match id_style {
+        IdStyle::Hash => format!("{:016x}", fnv1a(key.as_bytes())),
+        IdStyle::Slug => key,
+    }
+}
+
+fn get_connector_id(connector: &Connector, id_style: &IdStyle) -> String {
+    match id_style {
+        IdStyle::Hash => {
+            let key = format!(
+                "{}\0{}\0{}",
+                connector.connector_type,
+                connector.source_component_id,
+                connector.target_component_id
+            );
+            format!("{:016x}", fnv1a(key.as_bytes()))
+        }
+        IdStyle::Slug => format!(
+            "{}-{}->{}",
+            connector.source_component_id, connector.connector_type, connector.target_component_id
+        ),
+    }
 }
 
-fn get_random_id(len: usize) -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
-    let mut rng = rand::thread_rng();
-    let one_char = || CHARSET[rng.gen_range(0..CHARSET.len())] as char;
-    iter::repeat_with(one_char).take(len).collect()
+/// A canonical key of component values, e.g., "host=a,process_id=10012"
+pub fn get_component_key(component_values: &BTreeMap<String, String>) -> String {
+    component_values
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(identifier, value)| format!("{}={}", identifier, value))
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
-fn create_connector_if_not_exist(
-    model: &mut Model,
-    connector_type: String,
+/// Whether two value maps identify the same component, i.e., the non-empty values of the smaller
+/// one are all in the larger one.
+pub fn is_same_component(a: &BTreeMap<String, String>, b: &BTreeMap<String, String>) -> bool {
+    let (large_values, small_values) = if a.len() > b.len() { (a, b) } else { (b, a) };
+
+    small_values
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .all(|(identifier, value)| large_values.get(identifier) == Some(value))
+}
+
+// FNV-1a is used instead of std's hasher, whose output may change between Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+fn get_connector(
+    ci: &Ci,
+    position: usize,
     source_component_id: String,
     target_component_id: String,
-) {
-    let new_connector = Connector {
-        connector_type,
+) -> Connector {
+    let (_, procedure, _) = ci::split_execution_trace_id(&ci.id);
+
+    let mut connector = Connector::new(
+        ci.connector_type.clone(),
         source_component_id,
         target_component_id,
-    };
-
-    if !model.has_connector(&new_connector) {
-        model.connectors.push(new_connector);
+    );
+    connector.count = 1;
+    connector.execution_trace_ids.insert(ci.id.clone());
+    if !procedure.is_empty() {
+        connector.procedures.insert(procedure.to_string());
     }
+    connector.first_index = Some(position as u64);
+    connector.last_index = Some(position as u64);
+
+    connector
+}
+
+/// How a model is drawn in "png", "dot", and "html".
+#[derive(Debug, Default, Serialize)]
+pub struct RenderOptions {
+    pub clusters: Vec<Cluster>,
+
+    /// Scale the width of each edge by the number of its CIs.
+    pub scale_edges: bool,
 }
 
 pub fn write_model(
     model: Model,
+    options: &RenderOptions,
     output_file_path_str: &str,
     output_format_str: &str,
 ) -> Result<(), Box<dyn Error>> {
     match output_format_str {
         "json" => write_model_as_json(model, output_file_path_str)?,
-        "png" => write_model_as_png(model, output_file_path_str)?,
-        "dot" => write_model_as_dot(model, output_file_path_str)?,
-        _ => write_model_as_json(model, output_file_path_str)?,
+        "png" => write_model_as_png(model, options, output_file_path_str)?,
+        "dot" => write_model_as_dot(model, options, output_file_path_str)?,
+        _ => {
+            let result = export::export_model(&model, options, output_format_str)?;
+            print_result_str(&result, output_file_path_str)?
+        }
     }
 
     Ok(())
@@ -200,9 +483,13 @@ fn write_model_as_json(model: Model, output_file_path_str: &str) -> Result<(), B
     print_result_str(&result, output_file_path_str)
 }
 
-fn write_model_as_png(model: Model, output_file_path_str: &str) -> Result<(), Box<dyn Error>> {
+fn write_model_as_png(
+    model: Model,
+    options: &RenderOptions,
+    output_file_path_str: &str,
+) -> Result<(), Box<dyn Error>> {
     match exec(
-        get_dot_graph(model),
+        get_dot_graph(model, options),
         &mut PrinterContext::default(),
         vec![
             Format::Png.into(),
@@ -214,14 +501,22 @@ fn write_model_as_png(model: Model, output_file_path_str: &str) -> Result<(), Bo
     }
 }
 
-fn write_model_as_dot(model: Model, output_file_path_str: &str) -> Result<(), Box<dyn Error>> {
-    let result = get_dot_graph(model).print(&mut PrinterContext::default());
+fn write_model_as_dot(
+    model: Model,
+    options: &RenderOptions,
+    output_file_path_str: &str,
+) -> Result<(), Box<dyn Error>> {
+    let result = get_dot_graph(model, options).print(&mut PrinterContext::default());
     print_result_str(&result, output_file_path_str)
 }
 
-fn print_result_str(result: &str, file_path_str: &str) -> Result<(), Box<dyn Error>> {
+pub fn print_result_str(result: &str, file_path_str: &str) -> Result<(), Box<dyn Error>> {
     let p = Path::new(file_path_str);
-    let mut file = OpenOptions::new().write(true).create(true).open(p)?;
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(p)?;
 
     match file.write_all(result.as_bytes()) {
         Ok(_) => Ok(()),
@@ -229,25 +524,47 @@ fn print_result_str(result: &str, file_path_str: &str) -> Result<(), Box<dyn Err
     }
 }
 
-fn get_dot_graph(model: Model) -> Graph {
+fn get_dot_graph(model: Model, options: &RenderOptions) -> Graph {
     let mut g = graph!(di id!("model"));
 
-    for component in model.components {
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    for component in &model.components {
         let label = get_node_label(&component.component_values);
-        let node = node!(component.id;attr!("label", &label));
-        g.add_stmt(stmt!(node));
+        let node = node!(esc get_escaped_id(&component.id);attr!("label", &label));
+        nodes.insert(component.id.clone(), node);
+    }
+
+    let mut n = 0;
+    for cluster in &options.clusters {
+        g.add_stmt(stmt!(get_dot_subgraph(cluster, &mut nodes, &mut n)));
     }
 
+    // Components that are not in any cluster, in the order of the model.
+    for component in &model.components {
+        if let Some(node) = nodes.remove(&component.id) {
+            g.add_stmt(stmt!(node));
+        }
+    }
+
+    let max_count = model.connectors.iter().map(|c| c.count).max().unwrap_or(0);
     for connector in model.connectors {
-        let label = get_edge_label(&connector.connector_type);
-        let edge = edge!(node_id!(connector.source_component_id) => node_id!(connector.target_component_id);attr!("label", &label));
+        let label = get_edge_label(&connector);
+        let mut edge = edge!(node_id!(esc get_escaped_id(&connector.source_component_id)) => node_id!(esc get_escaped_id(&connector.target_component_id));attr!("label", &label));
+        if options.scale_edges {
+            let width = format!("{:.2}", get_edge_width(connector.count, max_count));
+            edge.attributes.push(attr!("penwidth", &width));
+        }
         g.add_stmt(stmt!(edge));
     }
 
     g
 }
 
-fn get_node_label(component_values: &HashMap<String, String>) -> String {
+pub fn get_escaped_id(id: &str) -> String {
+    id.replace('"', "\\\"")
+}
+
+pub fn get_node_label(component_values: &BTreeMap<String, String>) -> String {
     let mut label: String = String::from("\"");
     for (identifier, value) in component_values {
         if !value.is_empty() {
@@ -259,6 +576,45 @@ fn get_node_label(component_values: &HashMap<String, String>) -> String {
     label
 }
 
-fn get_edge_label(connector_type: &str) -> String {
-    format!("\"{}\"", connector_type)
+fn get_dot_subgraph(
+    cluster: &Cluster,
+    nodes: &mut HashMap<String, Node>,
+    n: &mut usize,
+) -> Subgraph {
+    // Graphviz draws a subgraph as a box only if its ID starts with "cluster".
+    let id = format!("cluster_{}", n);
+    *n += 1;
+
+    let label = format!("\"{}\"", get_escaped_id(&cluster.label));
+    let mut stmts = vec![stmt!(attr!("label", &label))];
+    for c in &cluster.clusters {
+        stmts.push(stmt!(get_dot_subgraph(c, nodes, n)));
+    }
+    for component_id in &cluster.component_ids {
+        if let Some(node) = nodes.remove(component_id) {
+            stmts.push(stmt!(node));
+        }
+    }
+
+    Subgraph {
+        id: id!(&id),
+        stmts,
+    }
+}
+
+/// An edge width from 1 to 5, which grows with the logarithm of the count.
+fn get_edge_width(count: usize, max_count: usize) -> f64 {
+    if max_count <= 1 {
+        return 1.0;
+    }
+
+    1.0 + 4.0 * (count.max(1) as f64).ln() / (max_count as f64).ln()
+}
+
+fn get_edge_label(connector: &Connector) -> String {
+    if connector.count > 1 {
+        format!("\"{} ({})\"", connector.connector_type, connector.count)
+    } else {
+        format!("\"{}\"", connector.connector_type)
+    }
 }
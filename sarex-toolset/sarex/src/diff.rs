@@ -0,0 +1,451 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use graphviz_rust::{
+    dot_generator::*, dot_structures::*, printer::DotPrinter, printer::PrinterContext,
+};
+use serde::Serialize;
+
+use crate::conn::{self, Component, Model};
+
+#[derive(Debug)]
+pub enum DiffError {
+    NoSuchEndpoint(String),
+}
+
+impl Error for DiffError {}
+
+impl Display for DiffError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DiffError::NoSuchEndpoint(id) => write!(
+                f,
+                "Connector {} has an endpoint that is not a component of its model",
+                id
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelDiff {
+    pub added_components: Vec<Component>,
+    pub removed_components: Vec<Component>,
+    pub changed_components: Vec<ChangedComponent>,
+    pub added_connectors: Vec<DiffConnector>,
+    pub removed_connectors: Vec<DiffConnector>,
+
+    #[serde(skip)]
+    nodes: Vec<DiffNode>,
+
+    #[serde(skip)]
+    edges: Vec<DiffEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedComponent {
+    pub before: Component,
+    pub after: Component,
+}
+
+/// A connector whose endpoints are given by their values, since component IDs differ between models.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffConnector {
+    pub connector_type: String,
+    pub source_component_values: BTreeMap<String, String>,
+    pub target_component_values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Unchanged,
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug)]
+struct DiffNode {
+    status: Status,
+    component_values: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+struct DiffEdge {
+    status: Status,
+    connector_type: String,
+    source: usize,
+    target: usize,
+}
+
+/// Compares two models. Components are matched on their values: first on exactly the same values,
+/// then on values that identify the same component (see `conn::is_same_component`), which are
+/// reported as changed. Connectors are matched on their types and matched endpoints. Fails if a
+/// connector has an endpoint that is not a component, since it cannot be matched.
+pub fn diff_models(a: &Model, b: &Model) -> Result<ModelDiff, DiffError> {
+    let mut nodes: Vec<DiffNode> = Vec::new();
+    let mut a_nodes: HashMap<&str, usize> = HashMap::new();
+    let mut b_nodes: HashMap<&str, usize> = HashMap::new();
+    let mut changed_components: Vec<ChangedComponent> = Vec::new();
+
+    for component in &a.components {
+        a_nodes.insert(&component.id, nodes.len());
+        nodes.push(DiffNode {
+            status: Status::Removed,
+            component_values: component.component_values.clone(),
+        });
+    }
+
+    let mut unmatched: Vec<&Component> = Vec::new();
+    for component in &b.components {
+        let key = conn::get_component_key(&component.component_values);
+        let matched = a.components.iter().find(|c| {
+            nodes[a_nodes[c.id.as_str()]].status == Status::Removed
+                && conn::get_component_key(&c.component_values) == key
+        });
+
+        match matched {
+            Some(c) => {
+                let i = a_nodes[c.id.as_str()];
+                nodes[i].status = Status::Unchanged;
+                b_nodes.insert(&component.id, i);
+            }
+            None => unmatched.push(component),
+        }
+    }
+
+    let mut added_components: Vec<Component> = Vec::new();
+    for component in unmatched {
+        let matched = a.components.iter().find(|c| {
+            nodes[a_nodes[c.id.as_str()]].status == Status::Removed
+                && conn::is_same_component(&c.component_values, &component.component_values)
+        });
+
+        match matched {
+            Some(c) => {
+                let i = a_nodes[c.id.as_str()];
+                nodes[i].status = Status::Changed;
+                nodes[i].component_values = component.component_values.clone();
+                b_nodes.insert(&component.id, i);
+                changed_components.push(ChangedComponent {
+                    before: c.clone(),
+                    after: component.clone(),
+                });
+            }
+            None => {
+                b_nodes.insert(&component.id, nodes.len());
+                nodes.push(DiffNode {
+                    status: Status::Added,
+                    component_values: component.component_values.clone(),
+                });
+                added_components.push(component.clone());
+            }
+        }
+    }
+
+    let removed_components = a
+        .components
+        .iter()
+        .filter(|c| nodes[a_nodes[c.id.as_str()]].status == Status::Removed)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let a_edges = get_edges(a, &a_nodes)?;
+    let b_edges = get_edges(b, &b_nodes)?;
+
+    let mut edges: Vec<DiffEdge> = Vec::new();
+    let mut added_connectors: Vec<DiffConnector> = Vec::new();
+    let mut removed_connectors: Vec<DiffConnector> = Vec::new();
+    for edge in a_edges.union(&b_edges) {
+        let status = match (a_edges.contains(edge), b_edges.contains(edge)) {
+            (true, true) => Status::Unchanged,
+            (false, true) => Status::Added,
+            _ => Status::Removed,
+        };
+
+        let (connector_type, source, target) = edge.clone();
+        let connector = DiffConnector {
+            connector_type: connector_type.clone(),
+            source_component_values: nodes[source].component_values.clone(),
+            target_component_values: nodes[target].component_values.clone(),
+        };
+        match status {
+            Status::Added => added_connectors.push(connector),
+            Status::Removed => removed_connectors.push(connector),
+            _ => {}
+        }
+
+        edges.push(DiffEdge {
+            status,
+            connector_type,
+            source,
+            target,
+        });
+    }
+
+    Ok(ModelDiff {
+        added_components,
+        removed_components,
+        changed_components,
+        added_connectors,
+        removed_connectors,
+        nodes,
+        edges,
+    })
+}
+
+impl ModelDiff {
+    /// Connectors that are in both models.
+    pub fn get_matched_connectors(&self) -> Vec<DiffConnector> {
+        self.edges
+            .iter()
+            .filter(|e| e.status == Status::Unchanged)
+            .map(|e| DiffConnector {
+                connector_type: e.connector_type.clone(),
+                source_component_values: self.nodes[e.source].component_values.clone(),
+                target_component_values: self.nodes[e.target].component_values.clone(),
+            })
+            .collect()
+    }
+}
+
+fn get_edges(
+    model: &Model,
+    node_indexes: &HashMap<&str, usize>,
+) -> Result<BTreeSet<(String, usize, usize)>, DiffError> {
+    model
+        .connectors
+        .iter()
+        .map(|c| {
+            let get_node = |id: &str| {
+                node_indexes
+                    .get(id)
+                    .copied()
+                    .ok_or_else(|| DiffError::NoSuchEndpoint(c.id.clone()))
+            };
+
+            Ok((
+                c.connector_type.clone(),
+                get_node(&c.source_component_id)?,
+                get_node(&c.target_component_id)?,
+            ))
+        })
+        .collect()
+}
+
+pub fn write_diff(
+    diff: &ModelDiff,
+    output_file_path_str: Option<&str>,
+    output_format_str: &str,
+) -> Result<(), Box<dyn Error>> {
+    let result = match output_format_str {
+        "json" => serde_json::to_string_pretty(diff)?,
+        "dot" => get_dot_graph(diff).print(&mut PrinterContext::default()),
+        _ => get_text(diff),
+    };
+
+    match output_file_path_str {
+        Some(p) => conn::print_result_str(&result, p),
+        None => {
+            println!("{}", result.trim_end());
+            Ok(())
+        }
+    }
+}
+
+fn get_text(diff: &ModelDiff) -> String {
+    let mut s = String::new();
+
+    s.push_str(&format!(
+        "components: +{} -{} ~{}\n",
+        diff.added_components.len(),
+        diff.removed_components.len(),
+        diff.changed_components.len()
+    ));
+    for c in &diff.added_components {
+        s.push_str(&format!(
+            "    + {}\n",
+            conn::get_component_key(&c.component_values)
+        ));
+    }
+    for c in &diff.removed_components {
+        s.push_str(&format!(
+            "    - {}\n",
+            conn::get_component_key(&c.component_values)
+        ));
+    }
+    for c in &diff.changed_components {
+        s.push_str(&format!(
+            "    ~ {} => {}\n",
+            conn::get_component_key(&c.before.component_values),
+            conn::get_component_key(&c.after.component_values)
+        ));
+    }
+
+    s.push_str(&format!(
+        "connectors: +{} -{}\n",
+        diff.added_connectors.len(),
+        diff.removed_connectors.len()
+    ));
+    for (sign, connectors) in [
+        ("+", &diff.added_connectors),
+        ("-", &diff.removed_connectors),
+    ] {
+        for c in connectors {
+            s.push_str(&format!(
+                "    {} {}: {} -> {}\n",
+                sign,
+                c.connector_type,
+                conn::get_component_key(&c.source_component_values),
+                conn::get_component_key(&c.target_component_values)
+            ));
+        }
+    }
+
+    s
+}
+
+fn get_color(status: Status) -> &'static str {
+    match status {
+        Status::Unchanged => "black",
+        Status::Added => "green",
+        Status::Removed => "red",
+        Status::Changed => "orange",
+    }
+}
+
+fn get_dot_graph(diff: &ModelDiff) -> Graph {
+    let mut g = graph!(di id!("diff"));
+
+    for (i, node) in diff.nodes.iter().enumerate() {
+        let label = conn::get_node_label(&node.component_values);
+        let color = get_color(node.status);
+        let id = format!("n{}", i);
+        let node = node!(&id;attr!("label", &label),attr!("color", &color));
+        g.add_stmt(stmt!(node));
+    }
+
+    for edge in &diff.edges {
+        let label = format!("\"{}\"", edge.connector_type);
+        let color = get_color(edge.status);
+        let style = if edge.status == Status::Removed {
+            "dashed"
+        } else {
+            "solid"
+        };
+        let e = edge!(node_id!(format!("n{}", edge.source)) => node_id!(format!("n{}", edge.target));attr!("label", &label),attr!("color", &color),attr!("style", &style));
+        g.add_stmt(stmt!(e));
+    }
+
+    g
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a model from component IDs with values, and connectors by their component IDs.
+    pub(crate) fn get_model(
+        components: &[(&str, &[(&str, &str)])],
+        connectors: &[(&str, &str, &str)],
+    ) -> Model {
+        serde_json::from_value(serde_json::json!({
+            "components": components
+                .iter()
+                .map(|(id, values)| serde_json::json!({
+                    "id": id,
+                    "component_values": values.iter().cloned().collect::<BTreeMap<_, _>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "connectors": connectors
+                .iter()
+                .map(|(connector_type, source, target)| serde_json::json!({
+                    "id": format!("{}-{}", source, target),
+                    "connector_type": connector_type,
+                    "source_component_id": source,
+                    "target_component_id": target,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn matches_components_by_values_not_ids() {
+        let a = get_model(
+            &[("a1", &[("host", "h1")]), ("a2", &[("host", "h2")])],
+            &[("Network", "a1", "a2")],
+        );
+        let b = get_model(
+            &[("b1", &[("host", "h1")]), ("b2", &[("host", "h2")])],
+            &[("Network", "b1", "b2")],
+        );
+
+        let diff = diff_models(&a, &b).unwrap();
+        assert!(diff.added_components.is_empty());
+        assert!(diff.removed_components.is_empty());
+        assert!(diff.changed_components.is_empty());
+        assert!(diff.added_connectors.is_empty());
+        assert!(diff.removed_connectors.is_empty());
+        assert_eq!(diff.get_matched_connectors().len(), 1);
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_components() {
+        let a = get_model(
+            &[("a1", &[("process_id", "1")]), ("a2", &[("host", "h2")])],
+            &[("File", "a1", "a2")],
+        );
+        let b = get_model(
+            &[
+                ("b1", &[("host", "h1"), ("process_id", "1")]),
+                ("b3", &[("host", "h3")]),
+            ],
+            &[("File", "b1", "b3")],
+        );
+
+        let diff = diff_models(&a, &b).unwrap();
+        assert_eq!(diff.changed_components.len(), 1);
+        assert_eq!(diff.changed_components[0].before.id, "a1");
+        assert_eq!(diff.changed_components[0].after.id, "b1");
+        assert_eq!(diff.added_components.len(), 1);
+        assert_eq!(diff.added_components[0].id, "b3");
+        assert_eq!(diff.removed_components.len(), 1);
+        assert_eq!(diff.removed_components[0].id, "a2");
+        assert_eq!(diff.added_connectors.len(), 1);
+        assert_eq!(diff.removed_connectors.len(), 1);
+    }
+
+    #[test]
+    fn distinguishes_connector_types() {
+        let a = get_model(
+            &[("a1", &[("host", "h1")]), ("a2", &[("host", "h2")])],
+            &[("Network", "a1", "a2")],
+        );
+        let b = get_model(
+            &[("b1", &[("host", "h1")]), ("b2", &[("host", "h2")])],
+            &[("HTTP", "b1", "b2")],
+        );
+
+        let diff = diff_models(&a, &b).unwrap();
+        assert_eq!(diff.added_connectors[0].connector_type, "HTTP");
+        assert_eq!(diff.removed_connectors[0].connector_type, "Network");
+    }
+
+    #[test]
+    fn fails_on_connectors_without_components() {
+        let a = get_model(
+            &[("a1", &[("host", "h1")])],
+            &[("Network", "a1", "missing")],
+        );
+        let b = get_model(&[("b1", &[("host", "h1")])], &[]);
+
+        assert!(matches!(
+            diff_models(&a, &b),
+            Err(DiffError::NoSuchEndpoint(id)) if id == "a1-missing"
+        ));
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::model::{drs::Dr, mapping_rules::MappingRule, projects::SourceFilter};
+
+const REGEX_PREFIX: &str = "re:";
+const EXCLUDE_PREFIX: &str = "!";
+
+/// Compiled patterns of a list, which match a name if any pattern that includes names matches it
+/// (or there is none) and no pattern that excludes names matches it.
+#[derive(Default)]
+pub struct Patterns {
+    includes: Vec<Pattern>,
+    excludes: Vec<Pattern>,
+}
+
+/// A compiled glob or regular expression. A glob is matched against the normalized name without
+/// parameters, and a regular expression against the name as it is extracted.
+struct Pattern {
+    regex: Regex,
+    is_glob: bool,
+}
+
+impl Pattern {
+    fn is_match(&self, name: &str, glob_name: &str) -> bool {
+        self.regex
+            .is_match(if self.is_glob { glob_name } else { name })
+    }
+}
+
+impl Patterns {
+    pub fn new(patterns: &[String]) -> Result<Self, regex::Error> {
+        let mut compiled = Patterns::default();
+        for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            match pattern.strip_prefix(EXCLUDE_PREFIX) {
+                Some(pattern) => compiled.excludes.push(compile_pattern(pattern)?),
+                None => compiled.includes.push(compile_pattern(pattern)?),
+            }
+        }
+
+        Ok(compiled)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.excludes.is_empty()
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        let glob_name = get_glob_name(name);
+
+        (self.includes.is_empty() || self.includes.iter().any(|p| p.is_match(name, &glob_name)))
+            && !self.excludes.iter().any(|p| p.is_match(name, &glob_name))
+    }
+}
+
+/// Decides which extracted dependency relations are from internal code to external code.
+pub struct SourceClassifier {
+    internal: Patterns,
+    external: Patterns,
+}
+
+impl SourceClassifier {
+    pub fn new(source_filter: &SourceFilter) -> Result<Self, regex::Error> {
+        Ok(SourceClassifier {
+            internal: Patterns::new(&source_filter.internal)?,
+            external: Patterns::new(&source_filter.external)?,
+        })
+    }
+
+    /// Whether the source is internal, and the target is external. Without external patterns,
+    /// any target that is not internal is external.
+    pub fn is_dependency(&self, dr: &Dr) -> bool {
+        !self.internal.is_empty()
+            && self.internal.is_match(&dr.source)
+            && !self.internal.is_match(&dr.target)
+            && (self.external.is_empty() || self.external.is_match(&dr.target))
+    }
+}
+
+/// A glob without wildcards matches a whole package or directory and everything in it, so
+/// "common" matches "common/Log.info" but not "commons-io/IOUtils.copy". "*" and "?" match within
+/// a part of a name, and "**" matches across parts. A glob that ends with "*" or "?" matches code
+/// at that level and its members only, so "edu.kaist.*" matches "edu/kaist/App.main" but not
+/// "edu/kaist/app/Main.run". A regular expression matches any part of a name as it is extracted,
+/// e.g., "re:^edu/kaist/(app|core)/" or "re:\.js$".
+fn compile_pattern(pattern: &str) -> Result<Pattern, regex::Error> {
+    if let Some(regex) = pattern.strip_prefix(REGEX_PREFIX) {
+        return Ok(Pattern {
+            regex: Regex::new(regex)?,
+            is_glob: false,
+        });
+    }
+
+    let glob = normalize(pattern.trim_end_matches(['.', '/']));
+    let mut s = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                s.push_str(".*");
+            }
+            '*' => s.push_str("[^/]*"),
+            '?' => s.push_str("[^/]"),
+            c => s.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    let last_part = glob.rsplit('/').next().unwrap_or_default();
+    if last_part == "**" {
+        s.push('$');
+    } else if last_part.contains(['*', '?']) {
+        s.push_str("(/[^/]*)?$");
+    } else {
+        s.push_str("(/.*)?$");
+    }
+
+    Ok(Pattern {
+        regex: Regex::new(&s)?,
+        is_glob: true,
+    })
+}
+
+/// Separates packages, classes, and procedures by "/", so "edu.kaist.App" and "edu/kaist/App"
+/// are the same, and so are "net/http.Get" and "net/http/Get".
+fn normalize(name: &str) -> String {
+    name.replace(['.', '\\'], "/")
+}
+
+/// The name that globs are matched against, which is normalized without parameters, e.g.,
+/// "edu/kaist/App/main" of "edu/kaist/App.main([Ljava/lang/String;)V", and
+/// "net/http/Client/Do" of "(*net/http.Client).Do".
+fn get_glob_name(name: &str) -> String {
+    match split_receiver(name) {
+        Some((receiver, method)) => normalize(&format!("{}.{}", receiver, method)),
+        None => normalize(name.split('(').next().unwrap_or_default()),
+    }
+}
+
+/// The receiver type and the name of a Go method, e.g., "net/http.Client" and "Do" of
+/// "(*net/http.Client).Do".
+fn split_receiver(name: &str) -> Option<(&str, &str)> {
+    let (receiver, method) = name.strip_prefix('(')?.split_once(").")?;
+
+    Some((receiver.trim_start_matches('*'), method))
+}
+
+/// Filters of dependency relations. Every given filter must match.
+#[derive(Default)]
+pub struct DrFilter {
+    pub target_prefix: Option<String>,
+    pub target_regex: Option<Regex>,
+    pub source_prefix: Option<String>,
+
+    /// Whether a dependency relation must have mapping rules, or must not
+    pub has_rules: Option<bool>,
+}
+
+impl DrFilter {
+    fn is_match(&self, entry: &DrEntry) -> bool {
+        let dr = entry.dr;
+        if let Some(prefix) = &self.target_prefix {
+            if !dr.target.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(regex) = &self.target_regex {
+            if !regex.is_match(&dr.target) {
+                return false;
+            }
+        }
+        if let Some(prefix) = &self.source_prefix {
+            if !dr.source.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(has_rules) = self.has_rules {
+            if (entry.rule_count > 0) != has_rules {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A dependency relation with the number of its mapping rules.
+pub struct DrEntry<'a> {
+    pub dr: &'a Dr,
+    pub rule_count: usize,
+}
+
+/// An external API, which is the target of dependency relations, with the entries that call it.
+pub struct Api<'a> {
+    pub target: &'a str,
+    pub callers: Vec<DrEntry<'a>>,
+}
+
+pub fn filter_drs<'a>(
+    drs: &'a [Dr],
+    mapping_rules: &[MappingRule],
+    filter: &DrFilter,
+) -> Vec<DrEntry<'a>> {
+    drs.iter()
+        .map(|dr| DrEntry {
+            dr,
+            rule_count: count_rules(dr, mapping_rules),
+        })
+        .filter(|entry| filter.is_match(entry))
+        .collect()
+}
+
+fn count_rules(dr: &Dr, mapping_rules: &[MappingRule]) -> usize {
+    let id = dr.id.map(|id| id.to_hex()).unwrap_or_default();
+
+    mapping_rules
+        .iter()
+        .filter(|r| {
+            r.relation.id == id
+                || (r.relation.source == dr.source && r.relation.target == dr.target)
+        })
+        .count()
+}
+
+/// Groups entries by their targets, which are sorted.
+pub fn group_by_api(entries: Vec<DrEntry>) -> Vec<Api> {
+    let mut apis: BTreeMap<&str, Vec<DrEntry>> = BTreeMap::new();
+    for entry in entries {
+        apis.entry(entry.dr.target.as_str())
+            .or_default()
+            .push(entry);
+    }
+
+    apis.into_iter()
+        .map(|(target, callers)| Api { target, callers })
+        .collect()
+}
+
+/// Groups APIs by their external libraries, which are sorted.
+pub fn group_by_library(apis: Vec<Api>) -> BTreeMap<String, Vec<Api>> {
+    let mut libraries: BTreeMap<String, Vec<Api>> = BTreeMap::new();
+    for api in apis {
+        libraries
+            .entry(get_library(api.target))
+            .or_default()
+            .push(api);
+    }
+
+    libraries
+}
+
+/// The external library of a target, e.g., "java/rmi/registry" for
+/// "java/rmi/registry/Registry.lookup(Ljava/lang/String;)Ljava/rmi/Remote;", "net/http" for
+/// "(*net/http.Client).Do", and "node-fetch" for "node-fetch/lib/index.js".
+pub fn get_library(target: &str) -> String {
+    let name = match split_receiver(target) {
+        Some((receiver, _)) => receiver,
+        None => target.split('(').next().unwrap_or(target),
+    };
+
+    // A JavaScript module, which is given by its file or its name
+    let is_js_file = [".js", ".mjs", ".cjs"]
+        .iter()
+        .any(|ext| name.ends_with(ext));
+    if is_js_file || !name.contains('.') {
+        let segments = if name.starts_with('@') { 2 } else { 1 };
+        return name.split('/').take(segments).collect::<Vec<_>>().join("/");
+    }
+
+    // A Java class or a Go package, followed by the name of a procedure
+    let owner = match name.rsplit_once('.') {
+        Some((owner, _)) => owner,
+        None => name,
+    };
+    match owner.rsplit_once('/') {
+        Some((package, class)) if class.starts_with(|c: char| c.is_uppercase()) => {
+            package.to_string()
+        }
+        _ => owner.to_string(),
+    }
+}
+
+/// The changes of the dependency relations of a project by a new extraction. Dependency relations
+/// are identified by their sources and targets.
+pub struct DrChanges<'a> {
+    /// Extracted dependency relations that are not stored yet
+    pub added: Vec<Dr>,
+
+    /// Stored dependency relations that are extracted again
+    pub kept: usize,
+
+    /// Stored dependency relations that are extracted again with different details, e.g., a line
+    pub updated: Vec<Dr>,
+
+    /// Stored dependency relations in the scope of the extraction that are not extracted anymore
+    pub disappeared: Vec<&'a Dr>,
+
+    /// Stored dependency relations in the scope of the extraction that are the same as an earlier
+    /// stored one
+    pub duplicates: Vec<&'a Dr>,
+}
+
+/// Compares extracted dependency relations with stored ones. Only stored dependency relations for
+/// which `in_scope` holds, e.g., those of the extracted language, can disappear or be duplicates,
+/// since an extraction does not see the others.
+pub fn get_changes<'a>(
+    extracted: Vec<Dr>,
+    existing: &'a [Dr],
+    in_scope: impl Fn(&Dr) -> bool,
+) -> DrChanges<'a> {
+    // The first of duplicates is kept.
+    let mut existing_drs: HashMap<(&str, &str), &Dr> = HashMap::new();
+    for dr in existing {
+        existing_drs.entry(get_key(dr)).or_insert(dr);
+    }
+
+    let (kept, disappeared, duplicates) = {
+        let extracted_keys = extracted.iter().map(get_key).collect::<HashSet<_>>();
+
+        let mut seen: HashSet<(&str, &str)> = HashSet::new();
+        let mut disappeared: Vec<&Dr> = Vec::new();
+        let mut duplicates: Vec<&Dr> = Vec::new();
+        for dr in existing {
+            if !seen.insert(get_key(dr)) {
+                if in_scope(dr) {
+                    duplicates.push(dr);
+                }
+            } else if !extracted_keys.contains(&get_key(dr)) && in_scope(dr) {
+                disappeared.push(dr);
+            }
+        }
+
+        let kept = extracted_keys
+            .iter()
+            .filter(|key| existing_drs.contains_key(*key))
+            .count();
+
+        (kept, disappeared, duplicates)
+    };
+
+    let mut extracted_keys: HashSet<(String, String)> = HashSet::new();
+    let mut added: Vec<Dr> = Vec::new();
+    let mut updated: Vec<Dr> = Vec::new();
+    for dr in extracted {
+        if !extracted_keys.insert((dr.source.clone(), dr.target.clone())) {
+            continue;
+        }
+
+        match existing_drs.get(&get_key(&dr)) {
+            Some(e) if e.details != dr.details => updated.push(Dr { id: e.id, ..dr }),
+            Some(_) => {}
+            None => added.push(dr),
+        }
+    }
+
+    DrChanges {
+        added,
+        kept,
+        updated,
+        disappeared,
+        duplicates,
+    }
+}
+
+fn get_key(dr: &Dr) -> (&str, &str) {
+    (&dr.source, &dr.target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::drs::DrDetails;
+    use bson::oid::ObjectId;
+
+    #[test]
+    fn get_library_of_targets() {
+        for (target, library) in [
+            (
+                "java/rmi/registry/Registry.lookup(Ljava/lang/String;)Ljava/rmi/Remote;",
+                "java/rmi/registry",
+            ),
+            ("net/http.Get", "net/http"),
+            ("(*net/http.Client).Do", "net/http"),
+            ("node-fetch/lib/index.js", "node-fetch"),
+            ("@aws-sdk/client-s3", "@aws-sdk/client-s3"),
+        ] {
+            assert_eq!(get_library(target), library);
+        }
+    }
+
+    #[test]
+    fn globs_match_go_methods_by_their_receivers() {
+        let patterns = Patterns::new(&["net/http".to_string()]).unwrap();
+        assert!(patterns.is_match("(*net/http.Client).Do"));
+        assert!(patterns.is_match("net/http.Get"));
+        assert!(!patterns.is_match("(*net/rpc.Client).Call"));
+
+        let patterns = Patterns::new(&["net/http.Client".to_string()]).unwrap();
+        assert!(patterns.is_match("(*net/http.Client).Do"));
+        assert!(!patterns.is_match("net/http.Get"));
+    }
+
+    fn get_dr(source: &str, target: &str, line: Option<u32>) -> Dr {
+        Dr {
+            id: None,
+            source: source.to_string(),
+            target: target.to_string(),
+            project_id: "p".to_string(),
+            details: DrDetails {
+                lang: Some("java".to_string()),
+                line,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn get_stored_dr(source: &str, target: &str, line: Option<u32>) -> Dr {
+        Dr {
+            id: Some(ObjectId::new()),
+            ..get_dr(source, target, line)
+        }
+    }
+
+    fn get_keys<'a>(drs: impl IntoIterator<Item = &'a Dr>) -> Vec<(&'a str, &'a str)> {
+        drs.into_iter().map(get_key).collect()
+    }
+
+    #[test]
+    fn get_changes_compares_extracted_with_stored() {
+        let existing = vec![
+            get_stored_dr("a/App.main", "java/io/File.<init>", Some(1)),
+            get_stored_dr("a/App.main", "java/net/Socket.<init>", Some(2)),
+            get_stored_dr("a/App.run", "java/io/File.<init>", Some(3)),
+        ];
+        let extracted = vec![
+            get_dr("a/App.main", "java/io/File.<init>", Some(1)),
+            get_dr("a/App.main", "java/net/Socket.<init>", Some(5)),
+            get_dr("a/App.save", "java/io/FileWriter.<init>", Some(7)),
+            get_dr("a/App.save", "java/io/FileWriter.<init>", Some(8)),
+        ];
+
+        let changes = get_changes(extracted, &existing, |_| true);
+
+        assert_eq!(
+            get_keys(&changes.added),
+            vec![("a/App.save", "java/io/FileWriter.<init>")]
+        );
+        assert_eq!(changes.added[0].details.line, Some(7));
+        assert_eq!(changes.kept, 2);
+        assert_eq!(changes.updated.len(), 1);
+        assert_eq!(changes.updated[0].id, existing[1].id);
+        assert_eq!(changes.updated[0].details.line, Some(5));
+        assert_eq!(
+            get_keys(changes.disappeared),
+            vec![("a/App.run", "java/io/File.<init>")]
+        );
+        assert!(changes.duplicates.is_empty());
+    }
+
+    #[test]
+    fn get_changes_keeps_the_first_of_stored_duplicates() {
+        let existing = vec![
+            get_stored_dr("a/App.main", "java/io/File.<init>", Some(1)),
+            get_stored_dr("a/App.main", "java/io/File.<init>", Some(2)),
+            get_stored_dr("a/App.run", "java/io/File.<init>", None),
+            get_stored_dr("a/App.run", "java/io/File.<init>", None),
+        ];
+        let extracted = vec![get_dr("a/App.main", "java/io/File.<init>", Some(2))];
+
+        let changes = get_changes(extracted, &existing, |_| true);
+
+        assert!(changes.added.is_empty());
+        assert_eq!(changes.kept, 1);
+        assert_eq!(changes.updated.len(), 1);
+        assert_eq!(changes.updated[0].id, existing[0].id);
+        assert_eq!(
+            changes
+                .disappeared
+                .iter()
+                .map(|dr| dr.id)
+                .collect::<Vec<_>>(),
+            vec![existing[2].id]
+        );
+        assert_eq!(
+            changes
+                .duplicates
+                .iter()
+                .map(|dr| dr.id)
+                .collect::<Vec<_>>(),
+            vec![existing[1].id, existing[3].id]
+        );
+    }
+
+    #[test]
+    fn get_changes_only_removes_stored_drs_in_scope() {
+        let mut go_dr = get_stored_dr("main.main", "os.Open", None);
+        go_dr.details.lang = Some("go".to_string());
+        let existing = vec![
+            get_stored_dr("a/App.main", "java/io/File.<init>", None),
+            get_stored_dr("b/Lib.init", "java/io/File.<init>", None),
+            get_stored_dr("b/Lib.init", "java/io/File.<init>", None),
+            go_dr,
+        ];
+
+        // An empty extraction of Java with the sources "a"
+        let in_sources = Patterns::new(&["a".to_string()]).unwrap();
+        let changes = get_changes(Vec::new(), &existing, |dr| {
+            dr.details.lang.as_deref() == Some("java") && in_sources.is_match(&dr.source)
+        });
+
+        assert!(changes.added.is_empty());
+        assert_eq!(changes.kept, 0);
+        assert_eq!(
+            get_keys(changes.disappeared),
+            vec![("a/App.main", "java/io/File.<init>")]
+        );
+        assert!(changes.duplicates.is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+use std::{collections::BTreeMap, error::Error};
+
+use serde::Serialize;
+
+use crate::conn::{self, Model};
+use crate::diff::{self, ChangedComponent, DiffConnector, DiffError};
+
+#[derive(Debug, Serialize)]
+pub struct Evaluation {
+    pub overall: Score,
+    pub connector_types: BTreeMap<String, Score>,
+    pub false_positives: Vec<DiffConnector>,
+    pub false_negatives: Vec<DiffConnector>,
+
+    /// Ground-truth components (`before`) that are matched with reconstructed components (`after`)
+    /// whose values are not exactly the same, but identify the same component
+    pub fuzzy_matches: Vec<ChangedComponent>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Score {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Score {
+    fn compute(&mut self) {
+        self.precision = get_ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        );
+        self.recall = get_ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        );
+        self.f1 = if self.precision + self.recall > 0.0 {
+            2.0 * self.precision * self.recall / (self.precision + self.recall)
+        } else {
+            0.0
+        };
+    }
+}
+
+fn get_ratio(n: usize, d: usize) -> f64 {
+    if d == 0 {
+        0.0
+    } else {
+        n as f64 / d as f64
+    }
+}
+
+/// Evaluates the connectors of a reconstructed model against a ground-truth model. Components and
+/// connectors are matched in the same way as `sarex model diff`.
+pub fn evaluate(model: &Model, truth: &Model) -> Result<Evaluation, DiffError> {
+    let diff = diff::diff_models(truth, model)?;
+
+    let mut overall = Score::default();
+    let mut connector_types: BTreeMap<String, Score> = BTreeMap::new();
+    for c in diff.get_matched_connectors() {
+        overall.true_positives += 1;
+        connector_types
+            .entry(c.connector_type)
+            .or_default()
+            .true_positives += 1;
+    }
+    for c in &diff.added_connectors {
+        overall.false_positives += 1;
+        connector_types
+            .entry(c.connector_type.clone())
+            .or_default()
+            .false_positives += 1;
+    }
+    for c in &diff.removed_connectors {
+        overall.false_negatives += 1;
+        connector_types
+            .entry(c.connector_type.clone())
+            .or_default()
+            .false_negatives += 1;
+    }
+
+    overall.compute();
+    for score in connector_types.values_mut() {
+        score.compute();
+    }
+
+    Ok(Evaluation {
+        overall,
+        connector_types,
+        false_positives: diff.added_connectors,
+        false_negatives: diff.removed_connectors,
+        fuzzy_matches: diff.changed_components,
+    })
+}
+
+pub fn print_evaluation(
+    evaluation: &Evaluation,
+    output_format_str: &str,
+) -> Result<(), Box<dyn Error>> {
+    match output_format_str {
+        "json" => println!("{}", serde_json::to_string_pretty(evaluation)?),
+        _ => print!("{}", get_text(evaluation)),
+    }
+
+    Ok(())
+}
+
+fn get_text(evaluation: &Evaluation) -> String {
+    let mut s = String::new();
+
+    s.push_str(&format!(
+        "{:<20} {:>5} {:>5} {:>5} {:>9} {:>9} {:>9}\n",
+        "connector type", "TP", "FP", "FN", "precision", "recall", "F1"
+    ));
+    for (connector_type, score) in &evaluation.connector_types {
+        s.push_str(&get_score_line(connector_type, score));
+    }
+    s.push_str(&get_score_line("(overall)", &evaluation.overall));
+
+    for (title, connectors) in [
+        ("false positives", &evaluation.false_positives),
+        ("false negatives", &evaluation.false_negatives),
+    ] {
+        s.push_str(&format!("\n{}: {}\n", title, connectors.len()));
+        for c in connectors {
+            s.push_str(&format!(
+                "    {}: {} -> {}\n",
+                c.connector_type,
+                conn::get_component_key(&c.source_component_values),
+                conn::get_component_key(&c.target_component_values)
+            ));
+        }
+    }
+
+    s.push_str(&format!(
+        "\nfuzzy matches: {}\n",
+        evaluation.fuzzy_matches.len()
+    ));
+    for c in &evaluation.fuzzy_matches {
+        s.push_str(&format!(
+            "    {} ~ {}\n",
+            conn::get_component_key(&c.before.component_values),
+            conn::get_component_key(&c.after.component_values)
+        ));
+    }
+
+    s
+}
+
+fn get_score_line(name: &str, score: &Score) -> String {
+    format!(
+        "{:<20} {:>5} {:>5} {:>5} {:>9.3} {:>9.3} {:>9.3}\n",
+        name,
+        score.true_positives,
+        score.false_positives,
+        score.false_negatives,
+        score.precision,
+        score.recall,
+        score.f1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::tests::get_model;
+
+    #[test]
+    fn scores_connectors_per_type() {
+        let truth = get_model(
+            &[
+                ("t1", &[("host", "h1")]),
+                ("t2", &[("host", "h2")]),
+                ("t3", &[("host", "h3")]),
+            ],
+            &[("Network", "t1", "t2"), ("File", "t1", "t3")],
+        );
+        let model = get_model(
+            &[
+                ("m1", &[("host", "h1")]),
+                ("m2", &[("host", "h2")]),
+                ("m3", &[("host", "h3")]),
+            ],
+            &[("Network", "m1", "m2"), ("Network", "m2", "m3")],
+        );
+
+        let evaluation = evaluate(&model, &truth).unwrap();
+        assert_eq!(evaluation.overall.true_positives, 1);
+        assert_eq!(evaluation.overall.false_positives, 1);
+        assert_eq!(evaluation.overall.false_negatives, 1);
+        assert_eq!(evaluation.overall.precision, 0.5);
+        assert_eq!(evaluation.overall.recall, 0.5);
+        assert_eq!(evaluation.overall.f1, 0.5);
+
+        let network = &evaluation.connector_types["Network"];
+        assert_eq!(network.precision, 0.5);
+        assert_eq!(network.recall, 1.0);
+        let file = &evaluation.connector_types["File"];
+        assert_eq!(file.recall, 0.0);
+        assert_eq!(file.f1, 0.0);
+        assert!(evaluation.fuzzy_matches.is_empty());
+    }
+
+    #[test]
+    fn reports_fuzzy_matches() {
+        let truth = get_model(
+            &[("t1", &[("process_id", "1")]), ("t2", &[("host", "h2")])],
+            &[("Network", "t1", "t2")],
+        );
+        let model = get_model(
+            &[
+                ("m1", &[("host", "h1"), ("process_id", "1")]),
+                ("m2", &[("host", "h2")]),
+            ],
+            &[("Network", "m1", "m2")],
+        );
+
+        let evaluation = evaluate(&model, &truth).unwrap();
+        assert_eq!(evaluation.overall.true_positives, 1);
+        assert_eq!(evaluation.fuzzy_matches.len(), 1);
+        assert_eq!(evaluation.fuzzy_matches[0].before.id, "t1");
+        assert_eq!(evaluation.fuzzy_matches[0].after.id, "m1");
+    }
+
+    #[test]
+    fn fails_on_connectors_without_components() {
+        let truth = get_model(&[("t1", &[("host", "h1")])], &[]);
+        let model = get_model(&[("m1", &[("host", "h1")])], &[("Network", "m1", "m2")]);
+
+        assert!(evaluate(&model, &truth).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use serde_json::{json, Map, Value};
+
+use crate::conn::{self, Model};
+
+/// Data fields of Cytoscape.js nodes, which identifiers with the same names must not replace.
+const RESERVED_FIELDS: &[&str] = &["id", "label", "parent", "source", "target"];
+
+/// Renders the model as Cytoscape.js elements. Each identifier value of a node is its own data
+/// field, so that selectors such as `node[host = "h1"]` can use it. An identifier that clashes
+/// with a field of Cytoscape.js is prefixed with "value_", e.g., "value_id".
+pub fn generate(model: &Model) -> Result<String, serde_json::Error> {
+    let nodes = model
+        .components
+        .iter()
+        .map(|component| {
+            let mut data = Map::new();
+            data.insert("id".to_string(), json!(component.id));
+            data.insert(
+                "label".to_string(),
+                json!(conn::get_component_key(&component.component_values)),
+            );
+            for (identifier, value) in &component.component_values {
+                data.insert(get_field(identifier), get_value(value));
+            }
+
+            json!({ "data": data })
+        })
+        .collect::<Vec<_>>();
+
+    let edges = model
+        .connectors
+        .iter()
+        .enumerate()
+        .map(|(i, connector)| {
+            // Models built before connectors had IDs have empty ones.
+            let id = if connector.id.is_empty() {
+                format!("e{}", i)
+            } else {
+                connector.id.clone()
+            };
+
+            json!({
+                "data": {
+                    "id": id,
+                    "source": connector.source_component_id,
+                    "target": connector.target_component_id,
+                    "connector_type": connector.connector_type,
+                    "count": connector.count,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let elements = json!({
+        "elements": {
+            "nodes": nodes,
+            "edges": edges,
+        }
+    });
+
+    serde_json::to_string_pretty(&elements)
+}
+
+fn get_field(identifier: &str) -> String {
+    if RESERVED_FIELDS.contains(&identifier) {
+        format!("value_{}", identifier)
+    } else {
+        identifier.to_string()
+    }
+}
+
+/// An integer value, e.g., a port, is a number so that selectors can compare it. Other values,
+/// including integers with leading zeros, are strings.
+fn get_value(value: &str) -> Value {
+    match value.parse::<i64>() {
+        Ok(n) if n.to_string() == value => json!(n),
+        _ => json!(value),
+    }
+}
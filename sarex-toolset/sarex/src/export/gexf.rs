@@ -0,0 +1,72 @@
+use crate::conn::{self, Model};
+
+use super::{escape_xml, get_identifiers};
+
+pub fn generate(model: &Model) -> String {
+    let identifiers = get_identifiers(model);
+
+    let mut s = String::new();
+    s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    s.push_str("<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n");
+    s.push_str("  <graph defaultedgetype=\"directed\" mode=\"static\">\n");
+    s.push_str("    <attributes class=\"node\">\n");
+    for (i, identifier) in identifiers.iter().enumerate() {
+        s.push_str(&format!(
+            "      <attribute id=\"{}\" title=\"{}\" type=\"string\"/>\n",
+            i,
+            escape_xml(identifier)
+        ));
+    }
+    s.push_str("    </attributes>\n");
+    s.push_str("    <attributes class=\"edge\">\n");
+    s.push_str("      <attribute id=\"0\" title=\"connector_type\" type=\"string\"/>\n");
+    s.push_str("    </attributes>\n");
+
+    s.push_str("    <nodes>\n");
+    for component in &model.components {
+        s.push_str(&format!(
+            "      <node id=\"{}\" label=\"{}\">\n",
+            escape_xml(&component.id),
+            escape_xml(&conn::get_component_key(&component.component_values))
+        ));
+        s.push_str("        <attvalues>\n");
+        for (i, identifier) in identifiers.iter().enumerate() {
+            if let Some(value) = component.component_values.get(*identifier) {
+                s.push_str(&format!(
+                    "          <attvalue for=\"{}\" value=\"{}\"/>\n",
+                    i,
+                    escape_xml(value)
+                ));
+            }
+        }
+        s.push_str("        </attvalues>\n");
+        s.push_str("      </node>\n");
+    }
+    s.push_str("    </nodes>\n");
+
+    s.push_str("    <edges>\n");
+    for (i, connector) in model.connectors.iter().enumerate() {
+        let connector_type = escape_xml(&connector.connector_type);
+        s.push_str(&format!(
+            "      <edge id=\"e{}\" source=\"{}\" target=\"{}\" label=\"{}\" weight=\"{}\">\n",
+            i,
+            escape_xml(&connector.source_component_id),
+            escape_xml(&connector.target_component_id),
+            connector_type,
+            connector.count.max(1)
+        ));
+        s.push_str("        <attvalues>\n");
+        s.push_str(&format!(
+            "          <attvalue for=\"0\" value=\"{}\"/>\n",
+            connector_type
+        ));
+        s.push_str("        </attvalues>\n");
+        s.push_str("      </edge>\n");
+    }
+    s.push_str("    </edges>\n");
+
+    s.push_str("  </graph>\n");
+    s.push_str("</gexf>\n");
+
+    s
+}
@@ -0,0 +1,68 @@
+use crate::conn::{self, Model};
+
+use super::{escape_xml, get_identifiers};
+
+pub fn generate(model: &Model) -> String {
+    let identifiers = get_identifiers(model);
+
+    let mut s = String::new();
+    s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    s.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    s.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    for (i, identifier) in identifiers.iter().enumerate() {
+        s.push_str(&format!(
+            "  <key id=\"v{}\" for=\"node\" attr.name=\"{}\" attr.type=\"string\"/>\n",
+            i,
+            escape_xml(identifier)
+        ));
+    }
+    s.push_str(
+        "  <key id=\"connector_type\" for=\"edge\" attr.name=\"connector_type\" attr.type=\"string\"/>\n",
+    );
+    s.push_str("  <key id=\"count\" for=\"edge\" attr.name=\"count\" attr.type=\"int\"/>\n");
+    s.push_str("  <graph id=\"model\" edgedefault=\"directed\">\n");
+
+    for component in &model.components {
+        s.push_str(&format!(
+            "    <node id=\"{}\">\n",
+            escape_xml(&component.id)
+        ));
+        s.push_str(&format!(
+            "      <data key=\"label\">{}</data>\n",
+            escape_xml(&conn::get_component_key(&component.component_values))
+        ));
+        for (i, identifier) in identifiers.iter().enumerate() {
+            if let Some(value) = component.component_values.get(*identifier) {
+                s.push_str(&format!(
+                    "      <data key=\"v{}\">{}</data>\n",
+                    i,
+                    escape_xml(value)
+                ));
+            }
+        }
+        s.push_str("    </node>\n");
+    }
+
+    for (i, connector) in model.connectors.iter().enumerate() {
+        s.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n",
+            i,
+            escape_xml(&connector.source_component_id),
+            escape_xml(&connector.target_component_id)
+        ));
+        s.push_str(&format!(
+            "      <data key=\"connector_type\">{}</data>\n",
+            escape_xml(&connector.connector_type)
+        ));
+        s.push_str(&format!(
+            "      <data key=\"count\">{}</data>\n",
+            connector.count
+        ));
+        s.push_str("    </edge>\n");
+    }
+
+    s.push_str("  </graph>\n");
+    s.push_str("</graphml>\n");
+
+    s
+}
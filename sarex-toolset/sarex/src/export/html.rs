@@ -0,0 +1,395 @@
+use serde::Serialize;
+
+use crate::conn::{Model, RenderOptions};
+
+const MODEL_PLACEHOLDER: &str = "/*SAREX_MODEL*/";
+const OPTIONS_PLACEHOLDER: &str = "/*SAREX_OPTIONS*/";
+
+/// Renders a single HTML file with the model embedded, which works offline without any library.
+pub fn generate(model: &Model, options: &RenderOptions) -> Result<String, serde_json::Error> {
+    // The template is split at its placeholders, so that a placeholder in a value of the model is
+    // not replaced.
+    let (head, rest) = HTML_TEMPLATE
+        .split_once(MODEL_PLACEHOLDER)
+        .expect("the template has the model placeholder");
+    let (middle, tail) = rest
+        .split_once(OPTIONS_PLACEHOLDER)
+        .expect("the template has the options placeholder");
+
+    Ok([
+        head,
+        &get_embedded_json(model)?,
+        middle,
+        &get_embedded_json(options)?,
+        tail,
+    ]
+    .concat())
+}
+
+fn get_embedded_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    // "</script>" or "<!--" would end or change the script element that embeds the value. "<" is
+    // only in strings of JSON, where it can be escaped.
+    Ok(serde_json::to_string(value)?.replace('<', "\\u003c"))
+}
+
+const HTML_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Execution View</title>
+<style>
+  html, body { margin: 0; height: 100%; font: 13px sans-serif; }
+  body { display: flex; }
+  #side { width: 300px; padding: 12px; box-sizing: border-box; border-right: 1px solid #ccc; overflow-y: auto; }
+  #side h2 { font-size: 14px; margin: 16px 0 6px; }
+  #side input[type=search] { width: 100%; box-sizing: border-box; padding: 4px; }
+  #side table { border-collapse: collapse; width: 100%; }
+  #side td { border-top: 1px solid #eee; padding: 2px 4px; word-break: break-all; vertical-align: top; }
+  #side tr.context td { color: #888; font-style: italic; }
+  #side ul { padding-left: 16px; margin: 0; }
+  #view { flex: 1; cursor: grab; }
+  #view.panning { cursor: grabbing; }
+  .node rect { fill: #fff; stroke: #333; stroke-width: 1.2; }
+  .node { cursor: pointer; }
+  .node.match rect { fill: #fff3b0; }
+  .node.selected rect { stroke: #d33; stroke-width: 2.5; }
+  .node.dimmed, .edge.dimmed { opacity: 0.15; }
+  .cluster rect { fill: rgba(70, 130, 180, 0.06); stroke: #4682b4; stroke-dasharray: 4 2; }
+  .cluster text { fill: #4682b4; font-size: 12px; }
+  .edge line { stroke: #666; }
+  .edge text { fill: #555; font-size: 11px; }
+  .hidden { display: none; }
+</style>
+</head>
+<body>
+<div id="side">
+  <input id="search" type="search" placeholder="Search component values">
+  <h2>Connector types</h2>
+  <div id="types"></div>
+  <h2>Component</h2>
+  <div id="details">Click a component to see its identifier values and connectors.</div>
+</div>
+<svg id="view">
+  <defs>
+    <marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8" markerHeight="8" orient="auto-start-reverse">
+      <path d="M 0 0 L 10 5 L 0 10 z" fill="#666"></path>
+    </marker>
+  </defs>
+  <g id="scene"><g id="clusters"></g><g id="edges"></g><g id="nodes"></g></g>
+</svg>
+<script id="model" type="application/json">/*SAREX_MODEL*/</script>
+<script id="options" type="application/json">/*SAREX_OPTIONS*/</script>
+<script>
+'use strict';
+
+const model = JSON.parse(document.getElementById('model').textContent);
+const options = JSON.parse(document.getElementById('options').textContent);
+const SVG_NS = 'http://www.w3.org/2000/svg';
+const NODE_WIDTH = 180;
+const LINE_HEIGHT = 15;
+
+function el(name, attrs, parent) {
+  const e = document.createElementNS(SVG_NS, name);
+  for (const [k, v] of Object.entries(attrs)) e.setAttribute(k, v);
+  if (parent) parent.appendChild(e);
+  return e;
+}
+
+function getLines(component) {
+  return Object.entries(component.component_values)
+    .filter(([, v]) => v !== '')
+    .map(([k, v]) => `${k}: ${v}`);
+}
+
+// Layout: a force-directed layout computed once, starting from a circle.
+const nodes = model.components.map((c, i) => {
+  const angle = (2 * Math.PI * i) / Math.max(model.components.length, 1);
+  const r = 60 * Math.sqrt(model.components.length) + 100;
+  return { component: c, lines: getLines(c), x: r * Math.cos(angle), y: r * Math.sin(angle), dx: 0, dy: 0 };
+});
+const nodeById = new Map(nodes.map((n) => [n.component.id, n]));
+const edges = model.connectors
+  .map((c) => ({ connector: c, source: nodeById.get(c.source_component_id), target: nodeById.get(c.target_component_id) }))
+  .filter((e) => e.source && e.target);
+
+// Clusters in pre-order, so that outer clusters are drawn behind inner ones.
+const clusters = [];
+function addCluster(cluster) {
+  const c = { label: cluster.label, nodes: cluster.component_ids.map((id) => nodeById.get(id)).filter(Boolean) };
+  clusters.push(c);
+  c.children = cluster.clusters.map(addCluster);
+  c.members = c.nodes.concat(...c.children.map((child) => child.members));
+  return c;
+}
+options.clusters.forEach(addCluster);
+
+// A step of the force-directed layout takes O(n^2) time, so the steps are limited to keep the page
+// responsive. A model too large for it is laid out on a grid, with members of clusters together.
+const MAX_LAYOUT_WORK = 2e7;
+const MIN_LAYOUT_STEPS = 20;
+
+function layoutGrid() {
+  const ordered = [];
+  const placed = new Set();
+  for (const n of clusters.flatMap((c) => c.nodes).concat(nodes)) {
+    if (!placed.has(n)) { placed.add(n); ordered.push(n); }
+  }
+  const columns = Math.ceil(Math.sqrt(ordered.length));
+  ordered.forEach((n, i) => {
+    n.x = (i % columns) * (NODE_WIDTH + 60);
+    n.y = Math.floor(i / columns) * 120;
+  });
+}
+
+(function layout() {
+  const steps = Math.min(300, Math.floor(MAX_LAYOUT_WORK / Math.max(nodes.length * nodes.length, 1)));
+  if (steps < MIN_LAYOUT_STEPS) {
+    layoutGrid();
+    return;
+  }
+
+  const k = 220;
+  for (let step = 0; step < steps; step++) {
+    const t = 20 * (1 - step / steps) + 1;
+    for (const n of nodes) { n.dx = 0; n.dy = 0; }
+    for (let i = 0; i < nodes.length; i++) {
+      for (let j = i + 1; j < nodes.length; j++) {
+        const a = nodes[i], b = nodes[j];
+        let dx = a.x - b.x, dy = a.y - b.y;
+        const d = Math.max(Math.hypot(dx, dy), 1);
+        const f = (k * k) / d;
+        dx = (dx / d) * f; dy = (dy / d) * f;
+        a.dx += dx; a.dy += dy; b.dx -= dx; b.dy -= dy;
+      }
+    }
+    for (const e of edges) {
+      if (e.source === e.target) continue;
+      let dx = e.source.x - e.target.x, dy = e.source.y - e.target.y;
+      const d = Math.max(Math.hypot(dx, dy), 1);
+      const f = (d * d) / k;
+      dx = (dx / d) * f; dy = (dy / d) * f;
+      e.source.dx -= dx; e.source.dy -= dy; e.target.dx += dx; e.target.dy += dy;
+    }
+    // Members of a cluster are pulled to its center.
+    for (const c of clusters) {
+      if (c.members.length < 2) continue;
+      const cx = c.members.reduce((sum, n) => sum + n.x, 0) / c.members.length;
+      const cy = c.members.reduce((sum, n) => sum + n.y, 0) / c.members.length;
+      for (const n of c.members) {
+        const dx = cx - n.x, dy = cy - n.y;
+        const d = Math.max(Math.hypot(dx, dy), 1);
+        const f = (d * d) / k;
+        n.dx += (dx / d) * f; n.dy += (dy / d) * f;
+      }
+    }
+    for (const n of nodes) {
+      const d = Math.max(Math.hypot(n.dx, n.dy), 1);
+      n.x += (n.dx / d) * Math.min(d, t);
+      n.y += (n.dy / d) * Math.min(d, t);
+    }
+  }
+})();
+
+// Rendering
+const svg = document.getElementById('view');
+const scene = document.getElementById('scene');
+const edgeLayer = document.getElementById('edges');
+const nodeLayer = document.getElementById('nodes');
+
+function getHeight(n) {
+  return Math.max(n.lines.length, 1) * LINE_HEIGHT + 10;
+}
+
+// The point where the line from the center of a node to (x, y) leaves its box.
+function getBorderPoint(n, x, y) {
+  const dx = x - n.x, dy = y - n.y;
+  const w = NODE_WIDTH / 2, h = getHeight(n) / 2;
+  const s = Math.min(dx === 0 ? Infinity : w / Math.abs(dx), dy === 0 ? Infinity : h / Math.abs(dy));
+  return [n.x + dx * s, n.y + dy * s];
+}
+
+function getBounds(c) {
+  let x1 = Infinity, y1 = Infinity, x2 = -Infinity, y2 = -Infinity;
+  for (const n of c.nodes) {
+    const h = getHeight(n);
+    x1 = Math.min(x1, n.x - NODE_WIDTH / 2); y1 = Math.min(y1, n.y - h / 2);
+    x2 = Math.max(x2, n.x + NODE_WIDTH / 2); y2 = Math.max(y2, n.y + h / 2);
+  }
+  for (const child of c.children) {
+    const b = getBounds(child);
+    x1 = Math.min(x1, b.x1); y1 = Math.min(y1, b.y1); x2 = Math.max(x2, b.x2); y2 = Math.max(y2, b.y2);
+  }
+  c.bounds = { x1: x1 - 12, y1: y1 - 28, x2: x2 + 12, y2: y2 + 12 };
+  return c.bounds;
+}
+
+const clusterLayer = document.getElementById('clusters');
+for (const c of clusters) {
+  const b = c.bounds || getBounds(c);
+  if (!isFinite(b.x1)) continue;
+  const g = el('g', { class: 'cluster' }, clusterLayer);
+  el('rect', { x: b.x1, y: b.y1, width: b.x2 - b.x1, height: b.y2 - b.y1, rx: 6 }, g);
+  el('text', { x: b.x1 + 8, y: b.y1 + 17 }, g).textContent = c.label;
+}
+
+// An edge width from 1 to 5, which grows with the logarithm of the count.
+const maxCount = Math.max(0, ...edges.map((e) => e.connector.count || 0));
+function getEdgeWidth(count) {
+  if (!options.scale_edges || maxCount <= 1) return 1.2;
+  return 1 + (4 * Math.log(Math.max(count || 0, 1))) / Math.log(maxCount);
+}
+
+for (const e of edges) {
+  e.el = el('g', { class: 'edge' }, edgeLayer);
+  const [x1, y1] = getBorderPoint(e.source, e.target.x, e.target.y);
+  const [x2, y2] = getBorderPoint(e.target, e.source.x, e.source.y);
+  el('line', { x1, y1, x2, y2, 'marker-end': 'url(#arrow)', 'stroke-width': getEdgeWidth(e.connector.count) }, e.el);
+  const label = el('text', { x: (x1 + x2) / 2, y: (y1 + y2) / 2 - 4, 'text-anchor': 'middle' }, e.el);
+  label.textContent = e.connector.count > 1 ? `${e.connector.connector_type} (${e.connector.count})` : e.connector.connector_type;
+}
+
+for (const n of nodes) {
+  const h = getHeight(n);
+  n.el = el('g', { class: 'node', transform: `translate(${n.x - NODE_WIDTH / 2}, ${n.y - h / 2})` }, nodeLayer);
+  el('rect', { width: NODE_WIDTH, height: h, rx: 4 }, n.el);
+  n.lines.forEach((line, i) => {
+    const text = el('text', { x: 6, y: 17 + i * LINE_HEIGHT }, n.el);
+    text.textContent = line.length > 28 ? line.slice(0, 27) + '…' : line;
+  });
+  el('title', {}, n.el).textContent = n.lines.join('\n');
+  n.el.addEventListener('click', (event) => { event.stopPropagation(); select(n); });
+}
+
+// Zoom and pan
+let view = { x: 0, y: 0, scale: 1 };
+function applyView() {
+  scene.setAttribute('transform', `translate(${view.x}, ${view.y}) scale(${view.scale})`);
+}
+function fit() {
+  if (nodes.length === 0) return;
+  const xs = nodes.map((n) => n.x), ys = nodes.map((n) => n.y);
+  const minX = Math.min(...xs) - NODE_WIDTH, maxX = Math.max(...xs) + NODE_WIDTH;
+  const minY = Math.min(...ys) - 60, maxY = Math.max(...ys) + 60;
+  const rect = svg.getBoundingClientRect();
+  view.scale = Math.min(rect.width / (maxX - minX), rect.height / (maxY - minY), 2);
+  view.x = rect.width / 2 - ((minX + maxX) / 2) * view.scale;
+  view.y = rect.height / 2 - ((minY + maxY) / 2) * view.scale;
+  applyView();
+}
+svg.addEventListener('wheel', (event) => {
+  event.preventDefault();
+  const rect = svg.getBoundingClientRect();
+  const px = event.clientX - rect.left, py = event.clientY - rect.top;
+  const factor = Math.exp(-event.deltaY * 0.001);
+  view.x = px - (px - view.x) * factor;
+  view.y = py - (py - view.y) * factor;
+  view.scale *= factor;
+  applyView();
+}, { passive: false });
+let pan = null;
+// Whether the last drag moved the view, so that its click does not clear the selection
+let panned = false;
+svg.addEventListener('mousedown', (event) => {
+  pan = { x: event.clientX - view.x, y: event.clientY - view.y, moved: false };
+  svg.classList.add('panning');
+});
+window.addEventListener('mousemove', (event) => {
+  if (!pan) return;
+  pan.moved = true;
+  view.x = event.clientX - pan.x;
+  view.y = event.clientY - pan.y;
+  applyView();
+});
+// A click on a node does not reach the svg, so panning ends here.
+window.addEventListener('mouseup', () => {
+  panned = pan !== null && pan.moved;
+  pan = null;
+  svg.classList.remove('panning');
+});
+svg.addEventListener('click', () => {
+  if (!panned) select(null);
+  panned = false;
+});
+window.addEventListener('resize', fit);
+
+// Filtering by connector types
+const hiddenTypes = new Set();
+const types = [...new Set(model.connectors.map((c) => c.connector_type))].sort();
+const typesDiv = document.getElementById('types');
+for (const type of types) {
+  const label = document.createElement('label');
+  const checkbox = document.createElement('input');
+  checkbox.type = 'checkbox';
+  checkbox.checked = true;
+  checkbox.addEventListener('change', () => {
+    if (checkbox.checked) hiddenTypes.delete(type); else hiddenTypes.add(type);
+    update();
+  });
+  label.append(checkbox, ' ' + type);
+  typesDiv.append(label, document.createElement('br'));
+}
+
+// Search and selection
+const search = document.getElementById('search');
+search.addEventListener('input', update);
+let selected = null;
+
+function select(n) {
+  selected = n;
+  const details = document.getElementById('details');
+  details.replaceChildren();
+  if (n) {
+    const table = document.createElement('table');
+    for (const [k, v] of Object.entries(n.component.component_values)) {
+      const row = table.insertRow();
+      row.insertCell().textContent = k;
+      row.insertCell().textContent = v;
+    }
+    const context = Object.entries(n.component.additional_component_values || {});
+    for (const [k, v] of context) {
+      const row = table.insertRow();
+      row.className = 'context';
+      row.insertCell().textContent = k;
+      row.insertCell().textContent = v.join(', ');
+    }
+    const id = document.createElement('p');
+    id.textContent = `ID: ${n.component.id}`;
+    const list = document.createElement('ul');
+    for (const e of edges) {
+      if (e.source !== n && e.target !== n) continue;
+      const item = document.createElement('li');
+      const other = e.source === n ? e.target : e.source;
+      const direction = e.source === n ? '→' : '←';
+      const count = e.connector.count > 1 ? ` (${e.connector.count} CIs)` : '';
+      item.textContent = `${direction} ${e.connector.connector_type}${count}: ${other.lines.join(', ')}`;
+      item.title = `ID: ${e.connector.id}`;
+      list.append(item);
+    }
+    const title = document.createElement('h2');
+    title.textContent = 'Connectors';
+    details.append(id, table, title, list);
+  } else {
+    details.textContent = 'Click a component to see its identifier values and connectors.';
+  }
+  update();
+}
+
+function update() {
+  const query = search.value.trim().toLowerCase();
+  for (const n of nodes) {
+    const match = query !== '' && Object.values(n.component.component_values).some((v) => v.toLowerCase().includes(query));
+    n.el.classList.toggle('match', match);
+    n.el.classList.toggle('selected', n === selected);
+    n.el.classList.toggle('dimmed', query !== '' && !match);
+  }
+  for (const e of edges) {
+    e.el.classList.toggle('hidden', hiddenTypes.has(e.connector.connector_type));
+    e.el.classList.toggle('dimmed', selected !== null && e.source !== selected && e.target !== selected);
+  }
+}
+
+fit();
+</script>
+</body>
+</html>
+"##;
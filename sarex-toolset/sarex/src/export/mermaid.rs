@@ -0,0 +1,50 @@
+use crate::conn::Model;
+
+use super::get_aliases;
+
+pub fn generate(model: &Model) -> String {
+    let aliases = get_aliases(model);
+
+    let mut s = String::new();
+    s.push_str("flowchart LR\n");
+    for component in &model.components {
+        let label = component
+            .component_values
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(identifier, value)| format!("{}: {}", escape(identifier), escape(value)))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        s.push_str(&format!(
+            "    {}[\"{}\"]\n",
+            aliases[component.id.as_str()],
+            label
+        ));
+    }
+
+    for connector in &model.connectors {
+        let (Some(source), Some(target)) = (
+            aliases.get(connector.source_component_id.as_str()),
+            aliases.get(connector.target_component_id.as_str()),
+        ) else {
+            continue;
+        };
+
+        s.push_str(&format!(
+            "    {} -->|\"{}\"| {}\n",
+            source,
+            escape(&connector.connector_type),
+            target
+        ));
+    }
+
+    s
+}
+
+// Mermaid takes HTML entities in quoted labels.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "#quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::conn::{Model, RenderOptions};
+
+mod cytoscape;
+mod gexf;
+mod graphml;
+mod html;
+mod mermaid;
+mod plantuml;
+mod structurizr;
+
+/// Formats rendered by this module, in addition to "json", "png", and "dot" of `conn::write_model`.
+pub const FORMATS: &[&str] = &[
+    "mermaid",
+    "plantuml",
+    "structurizr",
+    "graphml",
+    "gexf",
+    "cytoscape",
+    "html",
+];
+
+#[derive(Debug)]
+pub enum ExportError {
+    UnsupportedFormat(String),
+    FailedToSerialize(serde_json::Error),
+}
+
+impl Error for ExportError {}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ExportError::UnsupportedFormat(format) => write!(
+                f,
+                "Unsupported format: {}. Supported formats are json, png, dot, {}",
+                format,
+                FORMATS.join(", ")
+            ),
+            ExportError::FailedToSerialize(e) => write!(f, "Failed to serialize the model: {}", e),
+        }
+    }
+}
+
+/// Renders an execution view model in a text format of other tools, or as an HTML viewer.
+pub fn export_model(
+    model: &Model,
+    options: &RenderOptions,
+    format: &str,
+) -> Result<String, ExportError> {
+    match format {
+        "mermaid" => Ok(mermaid::generate(model)),
+        "plantuml" => Ok(plantuml::generate(model)),
+        "structurizr" => Ok(structurizr::generate(model)),
+        "graphml" => Ok(graphml::generate(model)),
+        "gexf" => Ok(gexf::generate(model)),
+        "cytoscape" => cytoscape::generate(model).map_err(ExportError::FailedToSerialize),
+        "html" => html::generate(model, options).map_err(ExportError::FailedToSerialize),
+        _ => Err(ExportError::UnsupportedFormat(format.to_string())),
+    }
+}
+
+/// Component IDs may contain characters that are not allowed as identifiers (e.g., slugs), so
+/// components are referred to by aliases such as "c0" in the exported model.
+fn get_aliases(model: &Model) -> HashMap<&str, String> {
+    model
+        .components
+        .iter()
+        .enumerate()
+        .map(|(i, component)| (component.id.as_str(), format!("c{}", i)))
+        .collect()
+}
+
+/// Collects the identifiers of all components, which become node attributes of graph formats.
+fn get_identifiers(model: &Model) -> BTreeSet<&String> {
+    model
+        .components
+        .iter()
+        .flat_map(|component| component.component_values.keys())
+        .collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,50 @@
+use crate::conn::Model;
+
+use super::get_aliases;
+
+pub fn generate(model: &Model) -> String {
+    let aliases = get_aliases(model);
+
+    let mut s = String::new();
+    s.push_str("@startuml\n");
+    s.push_str("left to right direction\n\n");
+    for component in &model.components {
+        let label = component
+            .component_values
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(identifier, value)| format!("{}: {}", escape(identifier), escape(value)))
+            .collect::<Vec<_>>()
+            .join("\\n");
+        s.push_str(&format!(
+            "component \"{}\" as {}\n",
+            label,
+            aliases[component.id.as_str()]
+        ));
+    }
+
+    s.push('\n');
+    for connector in &model.connectors {
+        let (Some(source), Some(target)) = (
+            aliases.get(connector.source_component_id.as_str()),
+            aliases.get(connector.target_component_id.as_str()),
+        ) else {
+            continue;
+        };
+
+        s.push_str(&format!(
+            "{} --> {} : {}\n",
+            source,
+            target,
+            escape(&connector.connector_type)
+        ));
+    }
+    s.push_str("@enduml\n");
+
+    s
+}
+
+// PlantUML has no escape sequence for a double quote in a quoted name.
+fn escape(s: &str) -> String {
+    s.replace('"', "'").replace('\n', " ")
+}
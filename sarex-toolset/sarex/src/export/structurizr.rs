@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use crate::conn::{self, Component, Model};
+
+use super::get_aliases;
+
+/// Components become containers of a single software system, since they are runtime elements.
+pub fn generate(model: &Model) -> String {
+    let aliases = get_aliases(model);
+    let names = get_names(&model.components);
+
+    let mut s = String::new();
+    s.push_str("workspace {\n\n");
+    s.push_str("    model {\n");
+    s.push_str("        system = softwareSystem \"System\" {\n");
+    for component in &model.components {
+        s.push_str(&format!(
+            "            {} = container \"{}\" {{\n",
+            aliases[component.id.as_str()],
+            escape(&names[component.id.as_str()])
+        ));
+        s.push_str("                properties {\n");
+        for (identifier, value) in &component.component_values {
+            if !value.is_empty() {
+                s.push_str(&format!(
+                    "                    \"{}\" \"{}\"\n",
+                    escape(identifier),
+                    escape(value)
+                ));
+            }
+        }
+        s.push_str("                }\n");
+        s.push_str("            }\n");
+    }
+    s.push_str("        }\n\n");
+
+    for connector in &model.connectors {
+        let (Some(source), Some(target)) = (
+            aliases.get(connector.source_component_id.as_str()),
+            aliases.get(connector.target_component_id.as_str()),
+        ) else {
+            continue;
+        };
+
+        let connector_type = escape(&connector.connector_type);
+        s.push_str(&format!(
+            "        {} -> {} \"{}\" \"{}\"\n",
+            source, target, connector_type, connector_type
+        ));
+    }
+    s.push_str("    }\n\n");
+
+    s.push_str("    views {\n");
+    s.push_str("        container system \"ExecutionView\" {\n");
+    s.push_str("            include *\n");
+    s.push_str("            autoLayout lr\n");
+    s.push_str("        }\n");
+    s.push_str("    }\n\n");
+    s.push_str("}\n");
+
+    s
+}
+
+/// Names of containers by component IDs. Structurizr rejects containers with the same name in a
+/// software system, so a component ID is added to an empty name or a name of several components.
+fn get_names(components: &[Component]) -> HashMap<&str, String> {
+    let keys = components
+        .iter()
+        .map(|c| conn::get_component_key(&c.component_values))
+        .collect::<Vec<_>>();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for key in &keys {
+        *counts.entry(key.as_str()).or_default() += 1;
+    }
+
+    components
+        .iter()
+        .zip(&keys)
+        .map(|(c, key)| {
+            let name = if key.is_empty() {
+                c.id.clone()
+            } else if counts[key.as_str()] > 1 {
+                format!("{} ({})", key, c.id)
+            } else {
+                key.clone()
+            };
+            (c.id.as_str(), name)
+        })
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', " ")
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::conn::{self, Component, Connector, IdStyle, Model};
+
+/// Groups components by the value of an identifier, e.g., "host", or by the directory of a path
+/// value, e.g., "file_path:dir".
+#[derive(Debug, Clone)]
+pub struct GroupRule {
+    pub identifier: String,
+    pub by_dir: bool,
+}
+
+#[derive(Debug)]
+pub enum GroupError {
+    MalformedRule(String),
+}
+
+impl Error for GroupError {}
+
+impl Display for GroupError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            GroupError::MalformedRule(rule) => write!(
+                f,
+                "Malformed grouping rule: {}. Use <identifier> or <identifier>:dir",
+                rule
+            ),
+        }
+    }
+}
+
+pub fn parse_rule(rule: &str) -> Result<GroupRule, GroupError> {
+    let (identifier, by_dir) = match rule.split_once(':') {
+        Some((identifier, "dir")) => (identifier, true),
+        Some(_) => return Err(GroupError::MalformedRule(rule.to_string())),
+        None => (rule, false),
+    };
+
+    if identifier.is_empty() {
+        return Err(GroupError::MalformedRule(rule.to_string()));
+    }
+
+    Ok(GroupRule {
+        identifier: identifier.to_string(),
+        by_dir,
+    })
+}
+
+/// The group of a component under the rule, or None if the component has no such value.
+fn get_group(rule: &GroupRule, component_values: &BTreeMap<String, String>) -> Option<String> {
+    let value = component_values
+        .get(&rule.identifier)
+        .filter(|value| !value.is_empty())?;
+
+    if !rule.by_dir {
+        return Some(value.clone());
+    }
+
+    let dir = Path::new(value)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if dir.is_empty() {
+        Some(".".to_string())
+    } else {
+        Some(dir)
+    }
+}
+
+/// A cluster of components, which may contain nested clusters of the next rule.
+#[derive(Debug, Serialize)]
+pub struct Cluster {
+    pub label: String,
+    pub component_ids: Vec<String>,
+    pub clusters: Vec<Cluster>,
+}
+
+/// Clusters components by the first rule, then clusters each cluster by the next rule, and so
+/// on. Components without a value of a rule are clustered by the next rule at the same level.
+pub fn get_clusters(model: &Model, rules: &[GroupRule]) -> Vec<Cluster> {
+    let components = model.components.iter().collect::<Vec<_>>();
+    let (clusters, _) = cluster(&components, rules);
+
+    clusters
+}
+
+fn cluster<'a>(
+    components: &[&'a Component],
+    rules: &[GroupRule],
+) -> (Vec<Cluster>, Vec<&'a Component>) {
+    let Some((rule, rest)) = rules.split_first() else {
+        return (Vec::new(), components.to_vec());
+    };
+
+    let mut groups: BTreeMap<String, Vec<&Component>> = BTreeMap::new();
+    let mut others: Vec<&Component> = Vec::new();
+    for component in components {
+        match get_group(rule, &component.component_values) {
+            Some(group) => groups.entry(group).or_default().push(component),
+            None => others.push(component),
+        }
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_iter()
+        .map(|(group, members)| {
+            let (clusters, members) = cluster(&members, rest);
+            Cluster {
+                label: format!("{}={}", rule.identifier, group),
+                component_ids: members.iter().map(|c| c.id.clone()).collect(),
+                clusters,
+            }
+        })
+        .collect();
+
+    let (other_clusters, others) = cluster(&others, rest);
+    clusters.extend(other_clusters);
+
+    (clusters, others)
+}
+
+/// Merges the components of the same group into one component, whose only value is the group,
+/// e.g., "file_path=/var/log". Connectors between merged components are aggregated with their
+/// statistics. Each component is merged by the first rule that applies to it.
+pub fn collapse_model(model: Model, rules: &[GroupRule], id_style: &IdStyle) -> Model {
+    let mut components: Vec<Component> = Vec::new();
+    let mut component_indexes: HashMap<String, usize> = HashMap::new();
+    let mut new_ids: HashMap<String, String> = HashMap::new();
+
+    for component in model.components {
+        let component_values = rules
+            .iter()
+            .find_map(|rule| {
+                get_group(rule, &component.component_values)
+                    .map(|group| BTreeMap::from([(rule.identifier.clone(), group)]))
+            })
+            .unwrap_or(component.component_values);
+
+        let key = conn::get_component_key(&component_values);
+        let i = *component_indexes.entry(key).or_insert_with(|| {
+            // A temporary ID, which is replaced by `conn::assign_ids`.
+            components.push(Component {
+                id: components.len().to_string(),
+                component_values,
+                ci_ids: Vec::new(),
+                ci_count: 0,
+                ci_ids_truncated: false,
+                additional_component_values: BTreeMap::new(),
+            });
+            components.len() - 1
+        });
+        components[i].ci_ids.extend(component.ci_ids);
+        components[i].ci_ids_truncated |= component.ci_ids_truncated;
+        for (identifier, values) in component.additional_component_values {
+            conn::add_bounded(
+                components[i]
+                    .additional_component_values
+                    .entry(identifier)
+                    .or_default(),
+                &values,
+                conn::MAX_ADDITIONAL_VALUES,
+            );
+        }
+        new_ids.insert(component.id, components[i].id.clone());
+    }
+
+    // A CI between two merged components is listed once.
+    for component in &mut components {
+        component.ci_ids.sort();
+        component.ci_ids.dedup();
+        if component.ci_ids.len() > conn::MAX_CI_IDS {
+            component.ci_ids.truncate(conn::MAX_CI_IDS);
+            component.ci_ids_truncated = true;
+        }
+    }
+
+    let mut connectors: Vec<Connector> = Vec::new();
+    let mut connector_indexes: HashMap<(String, String, String), usize> = HashMap::new();
+    for connector in model.connectors {
+        let (Some(source), Some(target)) = (
+            new_ids.get(&connector.source_component_id),
+            new_ids.get(&connector.target_component_id),
+        ) else {
+            continue;
+        };
+
+        let key = (
+            connector.connector_type.clone(),
+            source.clone(),
+            target.clone(),
+        );
+        let i = *connector_indexes.entry(key).or_insert_with(|| {
+            connectors.push(Connector::new(
+                connector.connector_type.clone(),
+                source.clone(),
+                target.clone(),
+            ));
+            connectors.len() - 1
+        });
+        connectors[i].merge(&connector);
+    }
+
+    // Each CI is a connector instance, so the CIs of a component are those of its connectors.
+    let component_indexes = components
+        .iter()
+        .enumerate()
+        .map(|(i, component)| (component.id.clone(), i))
+        .collect::<HashMap<_, _>>();
+    for connector in &connectors {
+        let source = component_indexes[&connector.source_component_id];
+        let target = component_indexes[&connector.target_component_id];
+        components[source].ci_count += connector.count;
+        if target != source {
+            components[target].ci_count += connector.count;
+        }
+    }
+
+    let mut collapsed_model = Model {
+        connectors,
+        components,
+    };
+    conn::assign_ids(&mut collapsed_model, id_style);
+
+    collapsed_model
+}
@@ -0,0 +1,202 @@
+use crate::model::mapping_rules::MappingRule;
+
+use super::{
+    get_identifiers, get_rule_id, quote, ProbeFile, Side, DEFAULT_TRACE_FILE, HOST, PROCESS_ID,
+    TRACE_FILE_ENV,
+};
+
+const PACKAGE: &str = "sarexprobe";
+
+pub fn generate(mapping_rules: &[MappingRule]) -> Vec<ProbeFile> {
+    let mut s = String::new();
+    s.push_str("// Code generated by sarex instrument. DO NOT EDIT.\n\n");
+    s.push_str("// Package sarexprobe records execution traces for the mapping rules. Call each probe in its\n");
+    s.push_str("// procedure, right before the call of the relation target.\n");
+    s.push_str(&format!("package {}\n\n", PACKAGE));
+    s.push_str("import (\n");
+    s.push_str("\t\"encoding/json\"\n");
+    s.push_str("\t\"fmt\"\n");
+    s.push_str("\t\"os\"\n");
+    s.push_str("\t\"strconv\"\n");
+    s.push_str("\t\"sync\"\n");
+    s.push_str(")\n\n");
+    s.push_str("type executionTrace struct {\n");
+    s.push_str("\tID           string            `json:\"id\"`\n");
+    s.push_str("\tSourceValues map[string]string `json:\"sourceValues\"`\n");
+    s.push_str("\tTargetValues map[string]string `json:\"targetValues\"`\n");
+    s.push_str("}\n\n");
+    s.push_str("var (\n");
+    s.push_str("\tmu      sync.Mutex\n");
+    s.push_str("\tindexes = map[string]int{}\n");
+    s.push_str(")\n\n");
+    s.push_str(
+        "func emit(ruleID, procedure string, sourceValues, targetValues map[string]string) {\n",
+    );
+    s.push_str("\tmu.Lock()\n");
+    s.push_str("\tdefer mu.Unlock()\n\n");
+    s.push_str("\tindex := indexes[ruleID]\n");
+    s.push_str("\tindexes[ruleID] = index + 1\n\n");
+    s.push_str("\tb, err := json.Marshal(executionTrace{\n");
+    s.push_str("\t\tID:           fmt.Sprintf(\"%s_%s_%d\", ruleID, procedure, index),\n");
+    s.push_str("\t\tSourceValues: sourceValues,\n");
+    s.push_str("\t\tTargetValues: targetValues,\n");
+    s.push_str("\t})\n");
+    s.push_str("\tif err != nil {\n");
+    s.push_str("\t\treturn\n");
+    s.push_str("\t}\n\n");
+    s.push_str(&format!(
+        "\ttraceFile := os.Getenv({})\n",
+        quote(TRACE_FILE_ENV)
+    ));
+    s.push_str("\tif traceFile == \"\" {\n");
+    s.push_str(&format!("\t\ttraceFile = {}\n", quote(DEFAULT_TRACE_FILE)));
+    s.push_str("\t}\n\n");
+    s.push_str("\tf, err := os.OpenFile(traceFile, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0644)\n");
+    s.push_str("\tif err != nil {\n");
+    s.push_str("\t\tfmt.Fprintln(os.Stderr, err)\n");
+    s.push_str("\t\treturn\n");
+    s.push_str("\t}\n");
+    s.push_str("\tdefer f.Close()\n\n");
+    s.push_str("\tfmt.Fprintln(f, string(b))\n");
+    s.push_str("}\n\n");
+    s.push_str("func processID() string {\n");
+    s.push_str("\treturn strconv.Itoa(os.Getpid())\n");
+    s.push_str("}\n\n");
+    s.push_str("func host() string {\n");
+    s.push_str("\th, _ := os.Hostname()\n");
+    s.push_str("\treturn h\n");
+    s.push_str("}\n");
+
+    for mapping_rule in mapping_rules {
+        s.push('\n');
+        s.push_str(&get_probe(mapping_rule));
+    }
+
+    vec![ProbeFile {
+        path: format!("{}/probes.go", PACKAGE),
+        content: s,
+    }]
+}
+
+fn get_probe(mapping_rule: &MappingRule) -> String {
+    let id = get_rule_id(mapping_rule);
+    let name = get_probe_name(mapping_rule, &id);
+
+    // Values that a Go program cannot know by itself are given by the caller of the probe.
+    let params = get_identifiers(mapping_rule)
+        .into_iter()
+        .filter(|(side, identifier)| !is_well_known(*side, identifier))
+        .collect::<Vec<_>>();
+
+    let mut s = String::new();
+    s.push_str(&format!(
+        "// {} records a \"{}\" connector instance of the mapping rule {}.\n",
+        name, mapping_rule.connector_type, id
+    ));
+    s.push_str(&format!(
+        "// Call it in {} right before {}.\n",
+        mapping_rule.procedure, mapping_rule.relation.target
+    ));
+    s.push_str(&format!("func {}(", name));
+    if !params.is_empty() {
+        s.push_str(
+            &params
+                .iter()
+                .map(|(side, identifier)| get_param_name(*side, identifier))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        s.push_str(" string");
+    }
+    s.push_str(") {\n");
+    s.push_str(&format!(
+        "\temit({}, {},\n",
+        quote(&id),
+        quote(&mapping_rule.procedure)
+    ));
+    s.push_str(&format!(
+        "\t\t{},\n",
+        get_values(
+            Side::Source,
+            &mapping_rule.source_component_identifier_schema
+        )
+    ));
+    s.push_str(&format!(
+        "\t\t{},\n",
+        get_values(
+            Side::Target,
+            &mapping_rule.target_component_identifier_schema
+        )
+    ));
+    s.push_str("\t)\n");
+    s.push_str("}\n");
+
+    s
+}
+
+fn get_values(side: Side, schema: &[String]) -> String {
+    let values = schema
+        .iter()
+        .map(|identifier| {
+            let value = match identifier.as_str() {
+                PROCESS_ID if is_well_known(side, identifier) => "processID()".to_string(),
+                HOST if is_well_known(side, identifier) => "host()".to_string(),
+                _ => get_param_name(side, identifier),
+            };
+
+            format!("{}: {}", quote(identifier), value)
+        })
+        .collect::<Vec<_>>();
+
+    format!("map[string]string{{{}}}", values.join(", "))
+}
+
+// The process that runs the probe is the source, so only its values are well known.
+fn is_well_known(side: Side, identifier: &str) -> bool {
+    matches!(side, Side::Source) && (identifier == PROCESS_ID || identifier == HOST)
+}
+
+// e.g., "github.com/foo/bar.(*Server).Start" of the rule "...a1b2c3" becomes "ProbeStartA1b2c3".
+fn get_probe_name(mapping_rule: &MappingRule, id: &str) -> String {
+    let func = mapping_rule
+        .procedure
+        .rsplit(['.', '/'])
+        .next()
+        .unwrap_or_default();
+    let suffix = &id[id.len().saturating_sub(6)..];
+
+    format!(
+        "Probe{}{}",
+        to_camel_case(func, true),
+        to_camel_case(suffix, true)
+    )
+}
+
+// e.g., "address" of the target becomes "targetAddress".
+fn get_param_name(side: Side, identifier: &str) -> String {
+    format!("{}{}", side.get_name(), to_camel_case(identifier, true))
+}
+
+fn to_camel_case(s: &str, upper_first: bool) -> String {
+    let mut result = String::new();
+    let mut upper = upper_first;
+    for c in s.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = !result.is_empty() || upper_first;
+            continue;
+        }
+
+        if result.is_empty() && c.is_ascii_digit() {
+            result.push('_');
+        }
+
+        if upper {
+            result.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,343 @@
+use std::collections::BTreeMap;
+
+use crate::model::mapping_rules::MappingRule;
+
+use super::{
+    get_identifiers, get_rule_id, quote, InstrumentError, ProbeFile, Side, DEFAULT_TRACE_FILE,
+    HOST, PROCESS_ID, THREAD_ID, TRACE_FILE_ENV,
+};
+
+const PACKAGE: &str = "sarex.probe";
+const PACKAGE_DIR: &str = "sarex/probe";
+
+/// A procedure of Java, e.g., `subscriber/EBAbstractSubscriber.register()Ljava/lang/Integer;`
+struct Procedure {
+    type_name: String,
+    method: String,
+    descriptor: String,
+}
+
+impl Procedure {
+    fn parse(procedure: &str) -> Option<Self> {
+        let (name, descriptor) = match procedure.find('(') {
+            Some(i) => procedure.split_at(i),
+            None => (procedure, ""),
+        };
+        let (type_name, method) = name.rsplit_once('.')?;
+
+        Some(Procedure {
+            type_name: type_name.replace('/', "."),
+            method: method.to_string(),
+            descriptor: descriptor.to_string(),
+        })
+    }
+
+    // The key is made of the origin of Advice, i.e., "#t|#m#d".
+    fn get_key(&self) -> String {
+        format!("{}|{}{}", self.type_name, self.method, self.descriptor)
+    }
+
+    fn get_matcher(&self) -> String {
+        let matcher = match self.method.as_str() {
+            "<init>" => "isConstructor()".to_string(),
+            method => format!("named({})", quote(method)),
+        };
+
+        if self.descriptor.is_empty() {
+            matcher
+        } else {
+            format!(
+                "{}.and(hasDescriptor({}))",
+                matcher,
+                quote(&self.descriptor)
+            )
+        }
+    }
+}
+
+/// Fails with the IDs of mapping rules whose procedures cannot be parsed, since no probe would
+/// record their traces.
+pub fn generate(mapping_rules: &[MappingRule]) -> Result<Vec<ProbeFile>, InstrumentError> {
+    let mut rules = Vec::new();
+    let mut invalid_ids = Vec::new();
+    for mapping_rule in mapping_rules {
+        match Procedure::parse(&mapping_rule.procedure) {
+            Some(procedure) => rules.push((mapping_rule, procedure)),
+            None => invalid_ids.push(get_rule_id(mapping_rule)),
+        }
+    }
+    if !invalid_ids.is_empty() {
+        return Err(InstrumentError::InvalidProcedures(invalid_ids));
+    }
+
+    Ok(vec![
+        ProbeFile {
+            path: format!("{}/SarexAgent.java", PACKAGE_DIR),
+            content: get_agent(&rules),
+        },
+        ProbeFile {
+            path: format!("{}/SarexAdvice.java", PACKAGE_DIR),
+            content: get_advice(),
+        },
+        ProbeFile {
+            path: format!("{}/SarexTrace.java", PACKAGE_DIR),
+            content: get_trace(&rules),
+        },
+        ProbeFile {
+            path: format!("{}/SarexValues.java", PACKAGE_DIR),
+            content: get_values(mapping_rules),
+        },
+        ProbeFile {
+            path: "META-INF/MANIFEST.MF".to_string(),
+            content: format!("Premain-Class: {}.SarexAgent\n", PACKAGE),
+        },
+    ])
+}
+
+fn get_agent(rules: &[(&MappingRule, Procedure)]) -> String {
+    // Group methods by their types, so that each type is transformed once.
+    let mut matchers: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (_, procedure) in rules {
+        let type_matchers = matchers.entry(&procedure.type_name).or_default();
+        let matcher = procedure.get_matcher();
+        if !type_matchers.contains(&matcher) {
+            type_matchers.push(matcher);
+        }
+    }
+
+    let mut s = String::new();
+    s.push_str(&format!("package {};\n\n", PACKAGE));
+    s.push_str("import static net.bytebuddy.matcher.ElementMatchers.*;\n\n");
+    s.push_str("import java.lang.instrument.Instrumentation;\n");
+    s.push_str("import net.bytebuddy.agent.builder.AgentBuilder;\n");
+    s.push_str("import net.bytebuddy.asm.Advice;\n\n");
+    s.push_str("/**\n");
+    s.push_str(
+        " * Generated by `sarex instrument`. A Java agent that records execution traces of the\n",
+    );
+    s.push_str(" * procedures in the mapping rules.\n");
+    s.push_str(" *\n");
+    s.push_str(" * Package this directory with ByteBuddy and META-INF/MANIFEST.MF into a JAR, and run the\n");
+    s.push_str(" * target software with -javaagent:<the JAR>.\n");
+    s.push_str(" */\n");
+    s.push_str("public final class SarexAgent {\n");
+    s.push_str("    public static void premain(String args, Instrumentation inst) {\n");
+    s.push_str("        new AgentBuilder.Default()\n");
+    for (type_name, type_matchers) in matchers {
+        s.push_str(&format!("            .type(named({}))\n", quote(type_name)));
+        s.push_str("            .transform((builder, type, classLoader, module, domain) -> builder.visit(\n");
+        s.push_str("                Advice.to(SarexAdvice.class).on(\n");
+        s.push_str(&format!(
+            "                    {})))\n",
+            type_matchers.join("\n                    .or(") + &")".repeat(type_matchers.len() - 1)
+        ));
+    }
+    s.push_str("            .installOn(inst);\n");
+    s.push_str("    }\n");
+    s.push_str("}\n");
+
+    s
+}
+
+fn get_advice() -> String {
+    let mut s = String::new();
+    s.push_str(&format!("package {};\n\n", PACKAGE));
+    s.push_str("import net.bytebuddy.asm.Advice;\n\n");
+    s.push_str("/** Generated by `sarex instrument`. */\n");
+    s.push_str("public final class SarexAdvice {\n");
+    s.push_str("    @Advice.OnMethodEnter\n");
+    s.push_str("    public static void enter(\n");
+    s.push_str("            @Advice.Origin(\"#t\") String type,\n");
+    s.push_str("            @Advice.Origin(\"#m\") String method,\n");
+    s.push_str("            @Advice.Origin(\"#d\") String descriptor,\n");
+    s.push_str("            @Advice.AllArguments Object[] args) {\n");
+    s.push_str("        SarexTrace.record(type, method, descriptor, args);\n");
+    s.push_str("    }\n");
+    s.push_str("}\n");
+
+    s
+}
+
+fn get_trace(rules: &[(&MappingRule, Procedure)]) -> String {
+    let mut s = String::new();
+    s.push_str(&format!("package {};\n\n", PACKAGE));
+    s.push_str("import java.io.FileWriter;\n");
+    s.push_str("import java.io.IOException;\n");
+    s.push_str("import java.io.PrintWriter;\n");
+    s.push_str("import java.util.ArrayList;\n");
+    s.push_str("import java.util.HashMap;\n");
+    s.push_str("import java.util.List;\n");
+    s.push_str("import java.util.Map;\n");
+    s.push_str("import java.util.concurrent.ConcurrentHashMap;\n");
+    s.push_str("import java.util.concurrent.atomic.AtomicLong;\n\n");
+    s.push_str("/** Generated by `sarex instrument`. Writes execution traces as JSON lines. */\n");
+    s.push_str("public final class SarexTrace {\n");
+    s.push_str(&format!(
+        "    private static final String TRACE_FILE =\n        System.getenv().getOrDefault({}, {});\n",
+        quote(TRACE_FILE_ENV),
+        quote(DEFAULT_TRACE_FILE)
+    ));
+    s.push_str("    private static final Map<String, List<Rule>> RULES = new HashMap<>();\n");
+    s.push_str(
+        "    private static final Map<String, AtomicLong> INDEXES = new ConcurrentHashMap<>();\n\n",
+    );
+    s.push_str("    static {\n");
+    for (mapping_rule, procedure) in rules {
+        s.push_str(&format!(
+            "        register({}, new Rule({}, {}, new String[] {{{}}}, new String[] {{{}}}));\n",
+            quote(&procedure.get_key()),
+            quote(&get_rule_id(mapping_rule)),
+            quote(&mapping_rule.procedure),
+            get_array(&mapping_rule.source_component_identifier_schema),
+            get_array(&mapping_rule.target_component_identifier_schema),
+        ));
+    }
+    s.push_str("    }\n\n");
+    s.push_str("    private SarexTrace() {}\n\n");
+    s.push_str("    private static void register(String key, Rule rule) {\n");
+    s.push_str("        RULES.computeIfAbsent(key, k -> new ArrayList<>()).add(rule);\n");
+    s.push_str("    }\n\n");
+    s.push_str("    public static void record(String type, String method, String descriptor, Object[] args) {\n");
+    s.push_str("        List<Rule> rules = RULES.get(type + \"|\" + method + descriptor);\n");
+    s.push_str("        if (rules == null) {\n");
+    s.push_str("            rules = RULES.get(type + \"|\" + method);\n");
+    s.push_str("        }\n");
+    s.push_str("        if (rules == null) {\n");
+    s.push_str("            return;\n");
+    s.push_str("        }\n\n");
+    s.push_str("        for (Rule rule : rules) {\n");
+    s.push_str("            long index = INDEXES.computeIfAbsent(rule.id, k -> new AtomicLong()).getAndIncrement();\n");
+    s.push_str("            StringBuilder sb = new StringBuilder();\n");
+    s.push_str("            sb.append(\"{\\\"id\\\":\").append(quote(rule.id + \"_\" + rule.procedure + \"_\" + index));\n");
+    s.push_str("            sb.append(\",\\\"sourceValues\\\":\").append(getValues(rule.id, \"source\", rule.sourceSchema, args));\n");
+    s.push_str("            sb.append(\",\\\"targetValues\\\":\").append(getValues(rule.id, \"target\", rule.targetSchema, args));\n");
+    s.push_str("            sb.append('}');\n");
+    s.push_str("            write(sb.toString());\n");
+    s.push_str("        }\n");
+    s.push_str("    }\n\n");
+    s.push_str(
+        "    private static String getValues(String ruleId, String side, String[] schema, Object[] args) {\n",
+    );
+    s.push_str("        StringBuilder sb = new StringBuilder(\"{\");\n");
+    s.push_str("        for (int i = 0; i < schema.length; i++) {\n");
+    s.push_str("            if (i > 0) {\n");
+    s.push_str("                sb.append(',');\n");
+    s.push_str("            }\n");
+    s.push_str("            String value = SarexValues.get(ruleId, side, schema[i], args);\n");
+    s.push_str("            sb.append(quote(schema[i])).append(':').append(quote(value == null ? \"\" : value));\n");
+    s.push_str("        }\n");
+    s.push_str("        return sb.append('}').toString();\n");
+    s.push_str("    }\n\n");
+    s.push_str("    private static synchronized void write(String line) {\n");
+    s.push_str(
+        "        try (PrintWriter out = new PrintWriter(new FileWriter(TRACE_FILE, true))) {\n",
+    );
+    s.push_str("            out.println(line);\n");
+    s.push_str("        } catch (IOException e) {\n");
+    s.push_str("            e.printStackTrace();\n");
+    s.push_str("        }\n");
+    s.push_str("    }\n\n");
+    s.push_str("    private static String quote(String value) {\n");
+    s.push_str("        StringBuilder sb = new StringBuilder(\"\\\"\");\n");
+    s.push_str("        for (char c : value.toCharArray()) {\n");
+    s.push_str("            if (c == '\"' || c == '\\\\') {\n");
+    s.push_str("                sb.append('\\\\').append(c);\n");
+    s.push_str("            } else if (c < 0x20) {\n");
+    s.push_str("                sb.append(String.format(\"\\\\u%04x\", (int) c));\n");
+    s.push_str("            } else {\n");
+    s.push_str("                sb.append(c);\n");
+    s.push_str("            }\n");
+    s.push_str("        }\n");
+    s.push_str("        return sb.append('\"').toString();\n");
+    s.push_str("    }\n\n");
+    s.push_str("    private static final class Rule {\n");
+    s.push_str("        final String id;\n");
+    s.push_str("        final String procedure;\n");
+    s.push_str("        final String[] sourceSchema;\n");
+    s.push_str("        final String[] targetSchema;\n\n");
+    s.push_str("        Rule(String id, String procedure, String[] sourceSchema, String[] targetSchema) {\n");
+    s.push_str("            this.id = id;\n");
+    s.push_str("            this.procedure = procedure;\n");
+    s.push_str("            this.sourceSchema = sourceSchema;\n");
+    s.push_str("            this.targetSchema = targetSchema;\n");
+    s.push_str("        }\n");
+    s.push_str("    }\n");
+    s.push_str("}\n");
+
+    s
+}
+
+fn get_values(mapping_rules: &[MappingRule]) -> String {
+    let mut s = String::new();
+    s.push_str(&format!("package {};\n\n", PACKAGE));
+    s.push_str("import java.net.InetAddress;\n\n");
+    s.push_str("/**\n");
+    s.push_str(" * Generated by `sarex instrument`. Values of component identifiers, which are recorded when a\n");
+    s.push_str(" * procedure is called. Fill in each TODO with a value taken from the arguments of the procedure.\n");
+    s.push_str(" * Values of the running process are only well known for the source, which calls the target.\n");
+    s.push_str(" */\n");
+    s.push_str("final class SarexValues {\n");
+    s.push_str("    private SarexValues() {}\n\n");
+    s.push_str(
+        "    static String get(String ruleId, String side, String identifier, Object[] args) {\n",
+    );
+    s.push_str(&format!(
+        "        switch ({}.equals(side) ? identifier : \"\") {{\n",
+        quote(Side::Source.get_name())
+    ));
+    s.push_str(&format!("            case {}:\n", quote(PROCESS_ID)));
+    s.push_str("                return String.valueOf(ProcessHandle.current().pid());\n");
+    s.push_str(&format!("            case {}:\n", quote(HOST)));
+    s.push_str("                return getHost();\n");
+    s.push_str(&format!("            case {}:\n", quote(THREAD_ID)));
+    s.push_str("                return String.valueOf(Thread.currentThread().getId());\n");
+    s.push_str("            default:\n");
+    s.push_str("                break;\n");
+    s.push_str("        }\n\n");
+    s.push_str("        switch (ruleId + \"/\" + side + \"/\" + identifier) {\n");
+    for mapping_rule in mapping_rules {
+        let id = get_rule_id(mapping_rule);
+        for (side, identifier) in get_identifiers(mapping_rule) {
+            let is_well_known = [PROCESS_ID, HOST, THREAD_ID].contains(&identifier.as_str());
+            if matches!(side, Side::Source) && is_well_known {
+                continue;
+            }
+
+            s.push_str(&format!(
+                "            // {} -> {}\n",
+                mapping_rule.procedure, mapping_rule.relation.target
+            ));
+            s.push_str(&format!(
+                "            case {}:\n",
+                quote(&format!("{}/{}/{}", id, side.get_name(), identifier))
+            ));
+            s.push_str(&format!(
+                "                return \"\"; // TODO: the value of {} of the {}\n",
+                identifier,
+                side.get_name()
+            ));
+        }
+    }
+    s.push_str("            default:\n");
+    s.push_str("                return \"\";\n");
+    s.push_str("        }\n");
+    s.push_str("    }\n\n");
+    s.push_str("    private static String getHost() {\n");
+    s.push_str("        try {\n");
+    s.push_str("            return InetAddress.getLocalHost().getHostName();\n");
+    s.push_str("        } catch (Exception e) {\n");
+    s.push_str("            return \"\";\n");
+    s.push_str("        }\n");
+    s.push_str("    }\n");
+    s.push_str("}\n");
+
+    s
+}
+
+fn get_array(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|i| quote(i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,185 @@
+use crate::model::mapping_rules::MappingRule;
+
+use super::{
+    get_rule_id, quote, ProbeFile, Side, DEFAULT_TRACE_FILE, HOST, PROCESS_ID, TRACE_FILE_ENV,
+};
+
+const HOOKS_FILE: &str = "sarex-hooks.js";
+
+pub fn generate(mapping_rules: &[MappingRule]) -> Vec<ProbeFile> {
+    let mut s = String::new();
+    s.push_str(
+        "// Generated by `sarex instrument`. Preload it with `node -r ./sarex-hooks.js <app>`.\n",
+    );
+    s.push_str("//\n");
+    s.push_str("// Each relation target is wrapped when it is loaded. A call of the target is recorded only\n");
+    s.push_str("// when its procedure is in the call stack. Fill in each TODO with a value taken from `args`,\n");
+    s.push_str("// the arguments given to the target.\n");
+    s.push_str("'use strict';\n\n");
+    s.push_str("const fs = require('fs');\n");
+    s.push_str("const os = require('os');\n");
+    s.push_str("const Module = require('module');\n\n");
+    s.push_str(&format!(
+        "const TRACE_FILE = process.env[{}] || {};\n\n",
+        quote(TRACE_FILE_ENV),
+        quote(DEFAULT_TRACE_FILE)
+    ));
+    s.push_str("const RULES = [\n");
+    for mapping_rule in mapping_rules {
+        s.push_str(&get_rule(mapping_rule));
+    }
+    s.push_str("];\n\n");
+    s.push_str(JS_RUNTIME);
+
+    vec![ProbeFile {
+        path: HOOKS_FILE.to_string(),
+        content: s,
+    }]
+}
+
+fn get_rule(mapping_rule: &MappingRule) -> String {
+    let mut s = String::new();
+    s.push_str("  {\n");
+    s.push_str(&format!("    id: {},\n", quote(&get_rule_id(mapping_rule))));
+    s.push_str(&format!(
+        "    connectorType: {},\n",
+        quote(&mapping_rule.connector_type)
+    ));
+    s.push_str(&format!(
+        "    procedure: {},\n",
+        quote(&mapping_rule.procedure)
+    ));
+    s.push_str(&format!(
+        "    target: {},\n",
+        quote(&mapping_rule.relation.target)
+    ));
+    s.push_str(&format!(
+        "    sourceSchema: [{}],\n",
+        get_array(&mapping_rule.source_component_identifier_schema)
+    ));
+    s.push_str(&format!(
+        "    targetSchema: [{}],\n",
+        get_array(&mapping_rule.target_component_identifier_schema)
+    ));
+    s.push_str(&get_values(
+        Side::Source,
+        &mapping_rule.source_component_identifier_schema,
+    ));
+    s.push_str(&get_values(
+        Side::Target,
+        &mapping_rule.target_component_identifier_schema,
+    ));
+    s.push_str("  },\n");
+
+    s
+}
+
+fn get_values(side: Side, schema: &[String]) -> String {
+    let mut s = String::new();
+    s.push_str(&format!("    {}Values: {{\n", side.get_name()));
+    for identifier in schema {
+        // The process that runs the probe is the source, so only its values are well known.
+        let value = match (side, identifier.as_str()) {
+            (Side::Source, PROCESS_ID) => "() => String(process.pid),".to_string(),
+            (Side::Source, HOST) => "() => os.hostname(),".to_string(),
+            _ => format!(
+                "(args) => '', // TODO: the value of {} of the {}",
+                identifier,
+                side.get_name()
+            ),
+        };
+        s.push_str(&format!("      {}: {}\n", quote(identifier), value));
+    }
+    s.push_str("    },\n");
+
+    s
+}
+
+fn get_array(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|i| quote(i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+const JS_RUNTIME: &str = r#"const indexes = new Map();
+const wrapped = new WeakMap();
+// Exports are cached, so a wrapper is found again when its module is required again.
+const wrappers = new WeakSet();
+
+function emit(rule, args) {
+  const index = indexes.get(rule.id) || 0;
+  indexes.set(rule.id, index + 1);
+
+  const getValues = (schema, values) =>
+    Object.fromEntries(schema.map((key) => [key, String(values[key](args) ?? '')]));
+
+  const trace = {
+    id: `${rule.id}_${rule.procedure}_${index}`,
+    sourceValues: getValues(rule.sourceSchema, rule.sourceValues),
+    targetValues: getValues(rule.targetSchema, rule.targetValues),
+  };
+
+  fs.appendFileSync(TRACE_FILE, JSON.stringify(trace) + '\n');
+}
+
+function stripExt(p) {
+  return p.replace(/\.[cm]?[jt]sx?$/, '');
+}
+
+function isCalledFrom(procedure) {
+  const stack = new Error().stack || '';
+  return stack.includes(stripExt(procedure));
+}
+
+function wrap(fn, rules) {
+  if (typeof fn !== 'function' || wrappers.has(fn)) return fn;
+  if (wrapped.has(fn)) return wrapped.get(fn);
+
+  const wrapper = function (...args) {
+    for (const rule of rules) {
+      if (isCalledFrom(rule.procedure)) emit(rule, args);
+    }
+    return new.target ? Reflect.construct(fn, args, new.target) : fn.apply(this, args);
+  };
+  Object.setPrototypeOf(wrapper, fn);
+  wrapped.set(fn, wrapper);
+  wrappers.add(wrapper);
+
+  return wrapper;
+}
+
+function wrapExports(exports, rules) {
+  if (typeof exports === 'function') return wrap(exports, rules);
+  if (exports === null || typeof exports !== 'object') return exports;
+
+  for (const key of Object.keys(exports)) {
+    const descriptor = Object.getOwnPropertyDescriptor(exports, key);
+    if (descriptor && descriptor.writable && typeof descriptor.value === 'function') {
+      exports[key] = wrap(descriptor.value, rules);
+    }
+  }
+
+  return exports;
+}
+
+function isTarget(filename, target) {
+  return filename === target || filename.endsWith(`/node_modules/${target}`);
+}
+
+const load = Module._load;
+Module._load = function (request, parent, isMain) {
+  const exports = load.apply(this, arguments);
+
+  let filename;
+  try {
+    filename = Module._resolveFilename(request, parent, isMain);
+  } catch (e) {
+    return exports;
+  }
+
+  const rules = RULES.filter((rule) => isTarget(filename, rule.target));
+  return rules.length > 0 ? wrapExports(exports, rules) : exports;
+};
+"#;
@@ -0,0 +1,138 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use crate::model::mapping_rules::MappingRule;
+
+mod go;
+mod java;
+mod js;
+
+/// The file that probes append execution traces to, unless SAREX_TRACE_FILE is set.
+const DEFAULT_TRACE_FILE: &str = "execution_traces.log";
+const TRACE_FILE_ENV: &str = "SAREX_TRACE_FILE";
+
+const PROCESS_ID: &str = "process_id";
+const HOST: &str = "host";
+const THREAD_ID: &str = "thread_id";
+
+/// A source file of generated probes, relative to the output directory.
+struct ProbeFile {
+    path: String,
+    content: String,
+}
+
+#[derive(Debug)]
+enum InstrumentError {
+    UnsupportedLanguage(String),
+    NoMappingRules,
+    InvalidProcedures(Vec<String>),
+}
+
+impl Error for InstrumentError {}
+
+impl Display for InstrumentError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            InstrumentError::UnsupportedLanguage(lang) => {
+                write!(f, "Probes cannot be generated for the language: {}", lang)
+            }
+            InstrumentError::NoMappingRules => write!(f, "No mapping rules in the project"),
+            InstrumentError::InvalidProcedures(ids) => write!(
+                f,
+                "Procedures of mapping rules cannot be parsed: {}",
+                ids.join(", ")
+            ),
+        }
+    }
+}
+
+/// Generates probes that record execution traces for the mapping rules, and writes them into the
+/// output directory. Every probe emits a JSON line of `ci::ExecutionTrace`, whose ID is
+/// `<MappingRuleId>_<Procedure>_<Index>`.
+pub fn write_probes(
+    mapping_rules: &[MappingRule],
+    lang: &str,
+    output_dir_str: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    if mapping_rules.is_empty() {
+        return Err(Box::new(InstrumentError::NoMappingRules));
+    }
+
+    let probe_files = match lang {
+        "java" => java::generate(mapping_rules)?,
+        "go" => go::generate(mapping_rules),
+        "js" => js::generate(mapping_rules),
+        _ => {
+            return Err(Box::new(InstrumentError::UnsupportedLanguage(
+                lang.to_string(),
+            )))
+        }
+    };
+
+    let output_dir = Path::new(output_dir_str);
+    let mut paths = Vec::new();
+    for probe_file in probe_files {
+        let p = output_dir.join(&probe_file.path);
+        if let Some(parent) = p.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&p)?;
+        file.write_all(probe_file.content.as_bytes())?;
+
+        paths.push(p.display().to_string());
+    }
+
+    Ok(paths)
+}
+
+fn get_rule_id(mapping_rule: &MappingRule) -> String {
+    match mapping_rule.id {
+        Some(id) => id.to_hex(),
+        None => "".to_string(),
+    }
+}
+
+/// Quotes a string as a literal, which is valid in Java, Go, and JavaScript.
+fn quote(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+/// A side of a connector. Values of the same identifier on both sides are recorded separately,
+/// e.g., `address` of the source and that of the target.
+#[derive(Clone, Copy)]
+enum Side {
+    Source,
+    Target,
+}
+
+impl Side {
+    fn get_name(&self) -> &'static str {
+        match self {
+            Side::Source => "source",
+            Side::Target => "target",
+        }
+    }
+}
+
+/// Collects the identifiers of both schemas with their sides, keeping their order.
+fn get_identifiers(mapping_rule: &MappingRule) -> Vec<(Side, &String)> {
+    let mut identifiers: Vec<(Side, &String)> = Vec::new();
+    for identifier in &mapping_rule.source_component_identifier_schema {
+        identifiers.push((Side::Source, identifier));
+    }
+    for identifier in &mapping_rule.target_component_identifier_schema {
+        identifiers.push((Side::Target, identifier));
+    }
+
+    identifiers
+}
@@ -1,12 +1,21 @@
 use cmd::init_app;
 use env_logger::Env;
 
+mod catalog;
+mod ci;
 mod cmd;
 mod config;
+mod conn;
+mod diff;
+mod dr;
+mod eval;
+mod export;
+mod group;
+mod instrument;
+mod matching;
 mod model;
 mod plugin;
-mod ci;
-mod conn;
+mod rule;
 
 #[tokio::main]
 async fn main() {
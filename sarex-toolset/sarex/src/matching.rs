@@ -0,0 +1,742 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::OpenOptions,
+    io::{BufReader, Write},
+};
+
+use log::warn;
+
+use crate::ci::Ci;
+use crate::conn;
+use crate::model::identity_policies::IdentityPolicy;
+
+const MAX_SAMPLES: usize = 5;
+
+type Values = BTreeMap<String, String>;
+
+/// Matches the values of CIs to components. The result does not depend on the order of CIs:
+///
+/// - The distinct sets of non-empty values are collected from the sources and targets of all CIs.
+/// - A set that is not a subset of any other set becomes a component. Sets that partially overlap,
+///   e.g., {host: a, process_id: 1} and {process_id: 1, thread_id: 5}, are different components.
+/// - A subset of other sets is merged into the component that contains it. If it is contained in
+///   more than one component, the match is ambiguous, so the subset is kept as its own component
+///   and reported.
+///
+/// Sets are found by an index of identifier/value pairs, instead of comparing every pair of sets.
+///
+/// Sets to which an identity policy applies are matched by the policy instead (see
+/// `match_by_policy`), and only with other sets of the same policy.
+pub struct ComponentMatcher {
+    set_indexes: HashMap<Values, usize>,
+    component_indexes: Vec<usize>,
+    components: Vec<Values>,
+    ambiguities: Vec<Ambiguity>,
+}
+
+/// A set of values that is contained in more than one component.
+pub struct Ambiguity {
+    pub values: Values,
+    pub candidates: Vec<Values>,
+}
+
+#[derive(Debug)]
+pub enum MatchingError {
+    NoIdentity(usize),
+}
+
+impl Error for MatchingError {}
+
+impl Display for MatchingError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            MatchingError::NoIdentity(i) => write!(
+                f,
+                "Identity policy #{} has neither required identifiers nor keys",
+                i + 1
+            ),
+        }
+    }
+}
+
+/// Reads identity policies from a JSON array, which is tried in order.
+pub fn read_policies(file_path_str: &str) -> Result<Vec<IdentityPolicy>, Box<dyn Error>> {
+    let file = OpenOptions::new().read(true).open(file_path_str)?;
+    let policies: Vec<IdentityPolicy> = serde_json::from_reader(BufReader::new(file))?;
+    validate_policies(&policies)?;
+
+    Ok(policies)
+}
+
+pub fn write_policies(
+    policies: &[IdentityPolicy],
+    output_file_path_str: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(output_file_path_str)?;
+    file.write_all(serde_json::to_string_pretty(policies)?.as_bytes())?;
+
+    Ok(())
+}
+
+pub fn validate_policies(policies: &[IdentityPolicy]) -> Result<(), MatchingError> {
+    match policies
+        .iter()
+        .position(|p| p.required.is_empty() && p.keys.iter().all(|k| k.is_empty()))
+    {
+        Some(i) => Err(MatchingError::NoIdentity(i)),
+        None => Ok(()),
+    }
+}
+
+impl ComponentMatcher {
+    pub fn new(cis: &[Ci], policies: &[IdentityPolicy]) -> Self {
+        // Sources first, so that components are ordered as they appear in the sources of CIs.
+        let mut sets: Vec<Values> = Vec::new();
+        let mut set_indexes: HashMap<Values, usize> = HashMap::new();
+        let mut set_policies: Vec<Option<usize>> = Vec::new();
+        let all_values = cis
+            .iter()
+            .map(|ci| (ci, &ci.source_component_values))
+            .chain(cis.iter().map(|ci| (ci, &ci.target_component_values)));
+        for (ci, values) in all_values {
+            let policy = policies
+                .iter()
+                .position(|p| is_applicable(p, &ci.connector_type, values));
+            let set = get_non_empty_values(values);
+            let i = *set_indexes.entry(set.clone()).or_insert_with(|| {
+                sets.push(set);
+                set_policies.push(None);
+                sets.len() - 1
+            });
+
+            // The first policy wins, wherever the set appears.
+            set_policies[i] = match (set_policies[i], policy) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+
+        let mut owners: Vec<usize> = (0..sets.len()).collect();
+        let mut owner_values: HashMap<usize, Values> = HashMap::new();
+        let mut ambiguities: Vec<Ambiguity> = Vec::new();
+
+        let members = (0..sets.len())
+            .filter(|i| set_policies[*i].is_none())
+            .collect::<Vec<_>>();
+        match_by_subsets(&sets, &members, &mut owners, &mut ambiguities);
+
+        for (p, policy) in policies.iter().enumerate() {
+            let members = (0..sets.len())
+                .filter(|i| set_policies[*i] == Some(p))
+                .collect::<Vec<_>>();
+            match_by_policy(
+                policy,
+                &sets,
+                &members,
+                &mut owners,
+                &mut owner_values,
+                &mut ambiguities,
+            );
+        }
+
+        // A component is placed where any of its sets appears first.
+        let mut component_of_owner: HashMap<usize, usize> = HashMap::new();
+        let mut components: Vec<Values> = Vec::new();
+        let mut component_indexes: Vec<usize> = Vec::with_capacity(sets.len());
+        for owner in &owners {
+            let c = *component_of_owner.entry(*owner).or_insert_with(|| {
+                components.push(
+                    owner_values
+                        .remove(owner)
+                        .unwrap_or_else(|| sets[*owner].clone()),
+                );
+                components.len() - 1
+            });
+            component_indexes.push(c);
+        }
+
+        Self {
+            set_indexes,
+            component_indexes,
+            components,
+            ambiguities,
+        }
+    }
+
+    /// The values of each component, which are indexed by `find`.
+    pub fn get_components(&self) -> &[Values] {
+        &self.components
+    }
+
+    /// The index of the component for the values of a CI.
+    pub fn find(&self, values: &HashMap<String, String>) -> Option<usize> {
+        let set = get_non_empty_values(values);
+
+        self.set_indexes
+            .get(&set)
+            .map(|i| self.component_indexes[*i])
+    }
+
+    pub fn log_ambiguities(&self) {
+        if self.ambiguities.is_empty() {
+            return;
+        }
+
+        warn!(
+            "{} component values match more than one component, so they are kept as separate components",
+            self.ambiguities.len()
+        );
+        for ambiguity in self.ambiguities.iter().take(MAX_SAMPLES) {
+            let mut candidates = ambiguity
+                .candidates
+                .iter()
+                .take(MAX_SAMPLES)
+                .map(get_key)
+                .collect::<Vec<_>>();
+            if ambiguity.candidates.len() > MAX_SAMPLES {
+                candidates.push(format!(
+                    "and {} more",
+                    ambiguity.candidates.len() - MAX_SAMPLES
+                ));
+            }
+
+            warn!(
+                "    {} matches {}",
+                get_key(&ambiguity.values),
+                candidates.join(" | ")
+            );
+        }
+    }
+}
+
+fn get_key(values: &Values) -> String {
+    let key = conn::get_component_key(values);
+    if key.is_empty() {
+        "(no values)".to_string()
+    } else {
+        key
+    }
+}
+
+fn get_non_empty_values(values: &HashMap<String, String>) -> Values {
+    values
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(identifier, value)| (identifier.clone(), value.clone()))
+        .collect()
+}
+
+/// Merges each set into the only maximal set that contains it, if any.
+fn match_by_subsets(
+    sets: &[Values],
+    members: &[usize],
+    owners: &mut [usize],
+    ambiguities: &mut Vec<Ambiguity>,
+) {
+    let mut index: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+    for i in members {
+        for (identifier, value) in &sets[*i] {
+            index
+                .entry((identifier.as_str(), value.as_str()))
+                .or_default()
+                .push(*i);
+        }
+    }
+
+    let supersets = members
+        .iter()
+        .map(|i| (*i, get_supersets(&sets[*i], sets, members, &index)))
+        .collect::<HashMap<_, _>>();
+
+    for i in members {
+        let maximal_supersets = supersets[i]
+            .iter()
+            .copied()
+            .filter(|j| supersets[j].is_empty())
+            .collect::<Vec<_>>();
+
+        match maximal_supersets.as_slice() {
+            [] => {}
+            [j] => owners[*i] = *j,
+            _ => ambiguities.push(Ambiguity {
+                values: sets[*i].clone(),
+                candidates: maximal_supersets.iter().map(|j| sets[*j].clone()).collect(),
+            }),
+        }
+    }
+}
+
+/// Merges sets that have the same values of the required identifiers and of any combination of
+/// `keys`, unless they differ in a value of another identifier of the policy. Other identifiers
+/// are ignored, and a component keeps the values of its largest set for them.
+///
+/// Larger sets are matched first, so the result does not depend on the order of CIs. A set that
+/// matches more than one component is ambiguous, and is kept as its own component.
+fn match_by_policy(
+    policy: &IdentityPolicy,
+    sets: &[Values],
+    members: &[usize],
+    owners: &mut [usize],
+    owner_values: &mut HashMap<usize, Values>,
+    ambiguities: &mut Vec<Ambiguity>,
+) {
+    let identifiers = policy
+        .required
+        .iter()
+        .chain(policy.optional.iter())
+        .chain(policy.keys.iter().flatten())
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>();
+    let is_consistent = |a: &Values, b: &Values| {
+        identifiers.iter().all(
+            |identifier| match (a.get(*identifier), b.get(*identifier)) {
+                (Some(x), Some(y)) => x == y,
+                _ => true,
+            },
+        )
+    };
+
+    let mut order = members.to_vec();
+    order.sort_by(|a, b| {
+        sets[*b]
+            .len()
+            .cmp(&sets[*a].len())
+            .then_with(|| sets[*a].cmp(&sets[*b]))
+    });
+
+    // The owner and the values of each group, and the groups of each identity key.
+    let mut groups: Vec<(usize, Values)> = Vec::new();
+    let mut key_groups: HashMap<(usize, Values), Vec<usize>> = HashMap::new();
+    for i in order {
+        let set = &sets[i];
+        let mut candidates = get_identity_keys(policy, set)
+            .iter()
+            .filter_map(|key| key_groups.get(key))
+            .flatten()
+            .copied()
+            .filter(|g| is_consistent(&groups[*g].1, set))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+
+        let g = match candidates.as_slice() {
+            [g] => *g,
+            _ => {
+                if candidates.len() > 1 {
+                    ambiguities.push(Ambiguity {
+                        values: set.clone(),
+                        candidates: candidates.iter().map(|g| groups[*g].1.clone()).collect(),
+                    });
+                }
+                groups.push((i, Values::new()));
+                groups.len() - 1
+            }
+        };
+
+        for (identifier, value) in set {
+            groups[g]
+                .1
+                .entry(identifier.clone())
+                .or_insert_with(|| value.clone());
+        }
+        for key in get_identity_keys(policy, &groups[g].1) {
+            let key_group = key_groups.entry(key).or_default();
+            if !key_group.contains(&g) {
+                key_group.push(g);
+            }
+        }
+        owners[i] = groups[g].0;
+    }
+
+    owner_values.extend(groups);
+}
+
+/// The keys of a set under the policy: each combination of `keys`, with the required identifiers,
+/// whose values are all in the set.
+fn get_identity_keys(policy: &IdentityPolicy, set: &Values) -> Vec<(usize, Values)> {
+    let no_keys = [Vec::new()];
+    let keys = if policy.keys.is_empty() {
+        &no_keys[..]
+    } else {
+        &policy.keys[..]
+    };
+
+    keys.iter()
+        .enumerate()
+        .filter_map(|(k, key)| {
+            let values = policy
+                .required
+                .iter()
+                .chain(key.iter())
+                .map(|identifier| Some((identifier.clone(), set.get(identifier)?.clone())))
+                .collect::<Option<Values>>()?;
+
+            Some((k, values))
+        })
+        .collect()
+}
+
+fn is_applicable(
+    policy: &IdentityPolicy,
+    connector_type: &str,
+    values: &HashMap<String, String>,
+) -> bool {
+    let is_same_type = match &policy.connector_type {
+        Some(t) => t == connector_type,
+        None => true,
+    };
+    let is_same_schema = match &policy.identifier_schema {
+        Some(schema) => {
+            schema.iter().map(String::as_str).collect::<BTreeSet<_>>()
+                == values.keys().map(String::as_str).collect::<BTreeSet<_>>()
+        }
+        None => true,
+    };
+
+    is_same_type && is_same_schema
+}
+
+/// The indexes of the sets that strictly contain the set. Candidates are taken from the shortest
+/// posting list of its pairs, since a superset must have all of them.
+fn get_supersets(
+    set: &Values,
+    sets: &[Values],
+    members: &[usize],
+    index: &HashMap<(&str, &str), Vec<usize>>,
+) -> Vec<usize> {
+    let is_superset = |j: &usize| {
+        let other = &sets[*j];
+        other.len() > set.len() && set.iter().all(|(k, v)| other.get(k) == Some(v))
+    };
+
+    let shortest = set
+        .iter()
+        .filter_map(|(identifier, value)| index.get(&(identifier.as_str(), value.as_str())))
+        .min_by_key(|posting_list| posting_list.len());
+
+    match shortest {
+        Some(posting_list) => posting_list
+            .iter()
+            .copied()
+            .filter(|j| is_superset(j))
+            .collect(),
+        // Every other set contains a set without values.
+        None => members.iter().copied().filter(|j| is_superset(j)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Builds CIs of a connector type from source values, with the same file as target.
+    fn get_cis(connector_type: &str, sources: &[&[(&str, &str)]]) -> Vec<Ci> {
+        sources
+            .iter()
+            .enumerate()
+            .map(|(i, source)| Ci {
+                id: i.to_string(),
+                connector_type: connector_type.to_string(),
+                source_component_values: get_values(source),
+                additional_source_component_values: HashMap::new(),
+                target_component_values: get_values(&[("file_path", "/tmp/out")]),
+            })
+            .collect()
+    }
+
+    fn get_policy(policy: serde_json::Value) -> IdentityPolicy {
+        serde_json::from_value(policy).unwrap()
+    }
+
+    fn find(matcher: &ComponentMatcher, pairs: &[(&str, &str)]) -> usize {
+        matcher.find(&get_values(pairs)).unwrap()
+    }
+
+    #[test]
+    fn subsets_merge_into_their_only_maximal_superset() {
+        let cis = get_cis(
+            "File",
+            &[
+                &[("process_id", "1")],
+                &[("host", "a"), ("process_id", "1")],
+                &[("host", "a"), ("process_id", "1"), ("thread_id", "")],
+            ],
+        );
+        let matcher = ComponentMatcher::new(&cis, &[]);
+
+        let component = find(&matcher, &[("host", "a"), ("process_id", "1")]);
+        assert_eq!(find(&matcher, &[("process_id", "1")]), component);
+        assert_eq!(matcher.get_components().len(), 2);
+        assert!(matcher.ambiguities.is_empty());
+    }
+
+    #[test]
+    fn subsets_with_several_maximal_supersets_are_ambiguous() {
+        let cis = get_cis(
+            "File",
+            &[
+                &[("process_id", "1")],
+                &[("host", "a"), ("process_id", "1")],
+                &[("host", "b"), ("process_id", "1")],
+            ],
+        );
+        let matcher = ComponentMatcher::new(&cis, &[]);
+
+        let a = find(&matcher, &[("host", "a"), ("process_id", "1")]);
+        let b = find(&matcher, &[("host", "b"), ("process_id", "1")]);
+        let pid = find(&matcher, &[("process_id", "1")]);
+        assert_ne!(a, b);
+        assert_ne!(pid, a);
+        assert_ne!(pid, b);
+        assert_eq!(matcher.ambiguities.len(), 1);
+        assert_eq!(matcher.ambiguities[0].candidates.len(), 2);
+    }
+
+    #[test]
+    fn policy_keeps_a_reused_process_id_on_two_hosts_apart() {
+        let policies = [get_policy(serde_json::json!({
+            "required": ["host", "process_id"],
+        }))];
+        let cis = get_cis(
+            "File",
+            &[
+                &[("host", "a"), ("process_id", "1")],
+                &[("host", "b"), ("process_id", "1")],
+                &[("host", "a"), ("process_id", "1"), ("thread_id", "7")],
+                &[("process_id", "1")],
+            ],
+        );
+        let matcher = ComponentMatcher::new(&cis, &policies);
+
+        let a = find(&matcher, &[("host", "a"), ("process_id", "1")]);
+        let b = find(&matcher, &[("host", "b"), ("process_id", "1")]);
+        assert_ne!(a, b);
+        // Identifiers outside the policy do not keep sets apart.
+        assert_eq!(
+            find(
+                &matcher,
+                &[("host", "a"), ("process_id", "1"), ("thread_id", "7")]
+            ),
+            a
+        );
+        // A set without the required identifiers has no identity, so it is not merged.
+        let pid = find(&matcher, &[("process_id", "1")]);
+        assert_ne!(pid, a);
+        assert_ne!(pid, b);
+        assert!(matcher.ambiguities.is_empty());
+    }
+
+    #[test]
+    fn optional_identifiers_refine_the_identity() {
+        let policies = [get_policy(serde_json::json!({
+            "required": ["host", "process_id"],
+            "optional": ["start_time"],
+        }))];
+        let cis = get_cis(
+            "File",
+            &[
+                &[("host", "a"), ("process_id", "1"), ("start_time", "t1")],
+                &[("host", "a"), ("process_id", "1"), ("start_time", "t2")],
+                &[
+                    ("host", "a"),
+                    ("process_id", "1"),
+                    ("start_time", "t1"),
+                    ("thread_id", "7"),
+                ],
+                &[("host", "b"), ("process_id", "2")],
+                &[("host", "b"), ("process_id", "2"), ("start_time", "t3")],
+                &[("host", "a"), ("process_id", "1")],
+            ],
+        );
+        let matcher = ComponentMatcher::new(&cis, &policies);
+
+        // Different values of an optional identifier keep sets apart.
+        let t1 = find(
+            &matcher,
+            &[("host", "a"), ("process_id", "1"), ("start_time", "t1")],
+        );
+        let t2 = find(
+            &matcher,
+            &[("host", "a"), ("process_id", "1"), ("start_time", "t2")],
+        );
+        assert_ne!(t1, t2);
+        assert_eq!(
+            find(
+                &matcher,
+                &[
+                    ("host", "a"),
+                    ("process_id", "1"),
+                    ("start_time", "t1"),
+                    ("thread_id", "7")
+                ]
+            ),
+            t1
+        );
+
+        // A missing value of an optional identifier merges into the only matching component.
+        assert_eq!(
+            find(&matcher, &[("host", "b"), ("process_id", "2")]),
+            find(
+                &matcher,
+                &[("host", "b"), ("process_id", "2"), ("start_time", "t3")]
+            )
+        );
+
+        // But it is ambiguous with more than one.
+        let unknown = find(&matcher, &[("host", "a"), ("process_id", "1")]);
+        assert_ne!(unknown, t1);
+        assert_ne!(unknown, t2);
+        assert_eq!(matcher.ambiguities.len(), 1);
+        assert_eq!(matcher.ambiguities[0].candidates.len(), 2);
+    }
+
+    #[test]
+    fn any_key_combination_merges_sets() {
+        let policies = [get_policy(serde_json::json!({
+            "keys": [["host", "process_id"], ["container_id"]],
+        }))];
+        let cis = get_cis(
+            "File",
+            &[
+                &[("host", "a"), ("process_id", "1"), ("container_id", "c1")],
+                &[("container_id", "c1")],
+                &[("host", "a"), ("process_id", "1")],
+                &[("host", "b"), ("process_id", "1")],
+            ],
+        );
+        let matcher = ComponentMatcher::new(&cis, &policies);
+
+        let component = find(
+            &matcher,
+            &[("host", "a"), ("process_id", "1"), ("container_id", "c1")],
+        );
+        assert_eq!(find(&matcher, &[("container_id", "c1")]), component);
+        assert_eq!(
+            find(&matcher, &[("host", "a"), ("process_id", "1")]),
+            component
+        );
+        assert_ne!(
+            find(&matcher, &[("host", "b"), ("process_id", "1")]),
+            component
+        );
+        assert_eq!(
+            matcher.get_components()[component],
+            get_non_empty_values(&get_values(&[
+                ("host", "a"),
+                ("process_id", "1"),
+                ("container_id", "c1")
+            ]))
+        );
+    }
+
+    #[test]
+    fn sets_outside_a_policy_fall_back_to_subset_matching() {
+        let policies = [get_policy(serde_json::json!({
+            "connectorType": "Network",
+            "required": ["host", "process_id"],
+        }))];
+        let mut cis = get_cis(
+            "File",
+            &[
+                &[("process_id", "1")],
+                &[("host", "a"), ("process_id", "1")],
+            ],
+        );
+        cis.extend(get_cis(
+            "Network",
+            &[
+                &[("process_id", "2")],
+                &[("host", "a"), ("process_id", "2")],
+            ],
+        ));
+        let matcher = ComponentMatcher::new(&cis, &policies);
+
+        assert_eq!(
+            find(&matcher, &[("process_id", "1")]),
+            find(&matcher, &[("host", "a"), ("process_id", "1")])
+        );
+        assert_ne!(
+            find(&matcher, &[("process_id", "2")]),
+            find(&matcher, &[("host", "a"), ("process_id", "2")])
+        );
+    }
+
+    #[test]
+    fn policy_applies_to_its_identifier_schema_only() {
+        let policy = get_policy(serde_json::json!({
+            "connectorType": "File",
+            "identifierSchema": ["host", "process_id"],
+            "required": ["host", "process_id"],
+        }));
+
+        assert!(is_applicable(
+            &policy,
+            "File",
+            &get_values(&[("host", "a"), ("process_id", "1")])
+        ));
+        assert!(!is_applicable(
+            &policy,
+            "Network",
+            &get_values(&[("host", "a"), ("process_id", "1")])
+        ));
+        assert!(!is_applicable(
+            &policy,
+            "File",
+            &get_values(&[("process_id", "1")])
+        ));
+        assert!(!is_applicable(
+            &policy,
+            "File",
+            &get_values(&[("host", "a"), ("process_id", "1"), ("thread_id", "7")])
+        ));
+    }
+
+    #[test]
+    fn first_applicable_policy_wins() {
+        let policies = [
+            get_policy(serde_json::json!({ "required": ["host", "process_id"] })),
+            get_policy(serde_json::json!({ "required": ["process_id"] })),
+        ];
+        let cis = get_cis(
+            "File",
+            &[
+                &[("host", "a"), ("process_id", "1")],
+                &[("host", "b"), ("process_id", "1")],
+            ],
+        );
+        let matcher = ComponentMatcher::new(&cis, &policies);
+
+        assert_ne!(
+            find(&matcher, &[("host", "a"), ("process_id", "1")]),
+            find(&matcher, &[("host", "b"), ("process_id", "1")])
+        );
+    }
+
+    #[test]
+    fn validate_policies_rejects_a_policy_without_identity() {
+        let valid = get_policy(serde_json::json!({ "keys": [["container_id"]] }));
+        let invalid = get_policy(serde_json::json!({
+            "optional": ["start_time"],
+            "keys": [[]],
+        }));
+
+        assert!(validate_policies(std::slice::from_ref(&valid)).is_ok());
+        assert!(matches!(
+            validate_policies(&[valid, invalid]),
+            Err(MatchingError::NoIdentity(1))
+        ));
+    }
+}
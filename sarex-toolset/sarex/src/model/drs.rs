@@ -1,12 +1,17 @@
-use super::mongo::{get_default_db, get_mongo_client};
-use bson::oid::ObjectId;
+use super::{
+    file,
+    mongo::{get_default_db, get_mongo_client},
+    store::Store,
+};
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
 use mongodb::Collection;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::{error::Error, str::FromStr};
 
 const DRS_COL: &str = "drs";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dr {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
@@ -15,16 +20,208 @@ pub struct Dr {
 
     #[serde(rename = "projectId")]
     pub project_id: String,
+
+    #[serde(flatten)]
+    pub details: DrDetails,
+}
+
+/// Where and how the source calls the target. Every field is optional, since extractors may not
+/// know them. If the source calls the target more than once, they are of the first call.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DrDetails {
+    /// The source file of the call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+
+    /// The offset of the call in the file, e.g., a byte offset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<CallKind>,
+
+    /// The module or package of the source
+    #[serde(
+        rename = "callerModule",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub caller_module: Option<String>,
+
+    /// The module or package of the target
+    #[serde(
+        rename = "calleeModule",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub callee_module: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    Static,
+    Virtual,
+    Interface,
+    Special,
+    Import,
+    DynamicImport,
+
+    #[serde(other)]
+    Other,
+}
+
+impl std::fmt::Display for CallKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            CallKind::Static => "static",
+            CallKind::Virtual => "virtual",
+            CallKind::Interface => "interface",
+            CallKind::Special => "special",
+            CallKind::Import => "import",
+            CallKind::DynamicImport => "dynamic import",
+            CallKind::Other => "other",
+        };
+
+        write!(f, "{}", kind)
+    }
+}
+
+pub async fn create_many(store: &Store, drs: Vec<&Dr>) -> Result<(), Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_drs_col(url).await?;
+
+            collection.insert_many(drs, None).await?;
+        }
+        Store::File(dir) => {
+            let new_drs = drs
+                .into_iter()
+                .map(|dr| Dr {
+                    id: Some(ObjectId::new()),
+                    ..dr.clone()
+                })
+                .collect::<Vec<_>>();
+
+            file::append(dir, DRS_COL, &new_drs)?;
+        }
+    }
+
+    Ok(())
 }
 
-pub async fn create_many(url: &str, drs: Vec<&Dr>) -> Result<(), Box<dyn Error>> {
-    let collection = get_drs_col(url).await?;
+/// Replaces stored dependency relations with the same IDs.
+pub async fn update_many(store: &Store, drs: &[Dr]) -> Result<(), Box<dyn Error>> {
+    if drs.is_empty() {
+        return Ok(());
+    }
 
-    collection.insert_many(drs, None).await?;
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_drs_col(url).await?;
+
+            for dr in drs {
+                if let Some(id) = dr.id {
+                    collection.replace_one(doc! {"_id": id}, dr, None).await?;
+                }
+            }
+        }
+        Store::File(dir) => {
+            let mut all_drs: Vec<Dr> = file::read_all(dir, DRS_COL)?;
+            for stored in all_drs.iter_mut() {
+                if let Some(dr) = drs.iter().find(|dr| dr.id.is_some() && dr.id == stored.id) {
+                    *stored = dr.clone();
+                }
+            }
+
+            file::write_all(dir, DRS_COL, &all_drs)?;
+        }
+    }
 
     Ok(())
 }
 
+pub async fn read_many(store: &Store, project_id: &str) -> Result<Vec<Dr>, Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_drs_col(url).await?;
+
+            let filter = doc! {"projectId": project_id};
+            let mut cursor = collection.find(filter, None).await?;
+
+            let mut drs: Vec<Dr> = Vec::new();
+            while let Some(dr) = cursor.try_next().await? {
+                drs.push(dr);
+            }
+
+            Ok(drs)
+        }
+        Store::File(dir) => {
+            let drs: Vec<Dr> = file::read_all(dir, DRS_COL)?;
+
+            Ok(drs
+                .into_iter()
+                .filter(|dr| dr.project_id == project_id)
+                .collect())
+        }
+    }
+}
+
+pub async fn read_one(store: &Store, id: &str) -> Result<Option<Dr>, Box<dyn Error>> {
+    let oid = ObjectId::from_str(id)?;
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_drs_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+
+            match collection.find_one(filter, None).await {
+                Ok(result) => Ok(result),
+                Err(e) => Err(Box::new(e)),
+            }
+        }
+        Store::File(dir) => {
+            let drs: Vec<Dr> = file::read_all(dir, DRS_COL)?;
+
+            Ok(drs.into_iter().find(|dr| dr.id == Some(oid)))
+        }
+    }
+}
+
+/// Deletes dependency relations by their IDs and returns how many were deleted.
+pub async fn delete_many(store: &Store, ids: &[ObjectId]) -> Result<u64, Box<dyn Error>> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_drs_col(url).await?;
+
+            let filter = doc! {"_id": {"$in": ids}};
+            let result = collection.delete_many(filter, None).await?;
+
+            Ok(result.deleted_count)
+        }
+        Store::File(dir) => {
+            let mut drs: Vec<Dr> = file::read_all(dir, DRS_COL)?;
+            let len = drs.len();
+            drs.retain(|dr| !dr.id.is_some_and(|id| ids.contains(&id)));
+
+            file::write_all(dir, DRS_COL, &drs)?;
+
+            Ok((len - drs.len()) as u64)
+        }
+    }
+}
+
 async fn get_drs_col(url: &str) -> Result<Collection<Dr>, Box<dyn Error>> {
     let client = get_mongo_client(url).await?;
     let db = get_default_db(&client)?;
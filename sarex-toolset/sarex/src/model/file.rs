@@ -0,0 +1,72 @@
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+const COLLECTION_EXT: &str = "jsonl";
+
+pub fn read_all<T: DeserializeOwned>(dir: &Path, col: &str) -> Result<Vec<T>, Box<dyn Error>> {
+    let p = get_col_path(dir, col);
+    if !p.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = OpenOptions::new().read(true).open(p)?;
+    let reader = BufReader::new(file);
+
+    let mut items: Vec<T> = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        items.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(items)
+}
+
+pub fn write_all<T: Serialize>(dir: &Path, col: &str, items: &[T]) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+
+    // Write to a temporary file first, so that a failure does not leave a half-written collection.
+    let p = get_col_path(dir, col);
+    let tmp = p.with_extension(format!("{}.tmp", COLLECTION_EXT));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp)?;
+    for item in items {
+        writeln!(file, "{}", serde_json::to_string(item)?)?;
+    }
+    file.sync_all()?;
+
+    fs::rename(tmp, p)?;
+
+    Ok(())
+}
+
+pub fn append<T: Serialize>(dir: &Path, col: &str, items: &[T]) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_col_path(dir, col))?;
+    for item in items {
+        writeln!(file, "{}", serde_json::to_string(item)?)?;
+    }
+
+    Ok(())
+}
+
+fn get_col_path(dir: &Path, col: &str) -> PathBuf {
+    dir.join(format!("{}.{}", col, COLLECTION_EXT))
+}
@@ -0,0 +1,139 @@
+use std::error::Error;
+
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
+use mongodb::{options::FindOptions, Collection};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    file,
+    mongo::{get_default_db, get_mongo_client},
+    store::Store,
+};
+
+const IDENTITYPOLICIES_COL: &str = "identitypolicies";
+
+/// Decides which identifier values make two component values the same component. A policy applies
+/// to the component values of CIs of its connector type, whose identifiers are its identifier
+/// schema. Either may be omitted to apply it more widely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityPolicy {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    #[serde(rename = "projectId", default)]
+    pub project_id: String,
+
+    /// The position of the policy in the list of its project, which is the order it is tried in
+    #[serde(default)]
+    pub order: u32,
+
+    #[serde(
+        rename = "connectorType",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub connector_type: Option<String>,
+
+    #[serde(
+        rename = "identifierSchema",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub identifier_schema: Option<Vec<String>>,
+
+    /// Identifiers that must be in every combination of `keys`
+    #[serde(default)]
+    pub required: Vec<String>,
+
+    /// Identifiers that do not identify a component, but must not differ when both values have them
+    #[serde(default)]
+    pub optional: Vec<String>,
+
+    /// Combinations of identifiers, any of which identifies a component with `required`
+    #[serde(default)]
+    pub keys: Vec<Vec<String>>,
+}
+
+/// Reads identity policies of a project in their order, which is the order they are tried in.
+pub async fn read_many(
+    store: &Store,
+    project_id: &str,
+) -> Result<Vec<IdentityPolicy>, Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"projectId": project_id};
+            let options = FindOptions::builder()
+                .sort(doc! {"order": 1, "_id": 1})
+                .build();
+            let mut cursor = collection.find(filter, options).await?;
+
+            let mut policies: Vec<IdentityPolicy> = Vec::new();
+            while let Some(policy) = cursor.try_next().await? {
+                policies.push(policy);
+            }
+
+            Ok(policies)
+        }
+        Store::File(dir) => {
+            let policies: Vec<IdentityPolicy> = file::read_all(dir, IDENTITYPOLICIES_COL)?;
+
+            let mut policies = policies
+                .into_iter()
+                .filter(|policy| policy.project_id == project_id)
+                .collect::<Vec<_>>();
+            policies.sort_by_key(|policy| policy.order);
+
+            Ok(policies)
+        }
+    }
+}
+
+/// Replaces all identity policies of a project, keeping their order.
+pub async fn replace_all(
+    store: &Store,
+    project_id: &str,
+    policies: Vec<IdentityPolicy>,
+) -> Result<(), Box<dyn Error>> {
+    let new_policies = policies
+        .into_iter()
+        .zip(0..)
+        .map(|(policy, order)| IdentityPolicy {
+            id: Some(ObjectId::new()),
+            project_id: project_id.to_string(),
+            order,
+            ..policy
+        })
+        .collect::<Vec<_>>();
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            collection
+                .delete_many(doc! {"projectId": project_id}, None)
+                .await?;
+            if !new_policies.is_empty() {
+                collection.insert_many(new_policies, None).await?;
+            }
+        }
+        Store::File(dir) => {
+            let mut all_policies: Vec<IdentityPolicy> = file::read_all(dir, IDENTITYPOLICIES_COL)?;
+            all_policies.retain(|policy| policy.project_id != project_id);
+            all_policies.extend(new_policies);
+
+            file::write_all(dir, IDENTITYPOLICIES_COL, &all_policies)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_col(url: &str) -> Result<Collection<IdentityPolicy>, Box<dyn Error>> {
+    let client = get_mongo_client(url).await?;
+    let db = get_default_db(&client)?;
+
+    Ok(db.collection(IDENTITYPOLICIES_COL))
+}
@@ -1,11 +1,15 @@
-use std::error::Error;
+use std::{error::Error, str::FromStr};
 
 use bson::{doc, oid::ObjectId};
 use futures::TryStreamExt;
 use mongodb::Collection;
 use serde::{Deserialize, Serialize};
 
-use super::mongo::{get_default_db, get_mongo_client};
+use super::{
+    file,
+    mongo::{get_default_db, get_mongo_client, MongoError},
+    store::Store,
+};
 
 const MAPPINGRULES_COL: &str = "mappingrules";
 
@@ -44,6 +48,13 @@ impl Clone for MappingRule {
     }
 }
 
+impl MappingRule {
+    /// Whether both mapping rules map the same procedure of the same dependency relation.
+    pub fn is_same_mapping(&self, other: &MappingRule) -> bool {
+        self.procedure == other.procedure && self.relation.is_same_dr(&other.relation)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Relation {
     #[serde(rename = "_id")]
@@ -67,18 +78,146 @@ impl Clone for Relation {
     }
 }
 
-pub async fn read_many(url: &str, project_id: &str) -> Result<Vec<MappingRule>, Box<dyn Error>> {
-    let collection = get_col(url).await?;
+impl Relation {
+    pub fn is_same_dr(&self, other: &Relation) -> bool {
+        self.source == other.source && self.target == other.target
+    }
+}
+
+pub async fn read_many(
+    store: &Store,
+    project_id: &str,
+) -> Result<Vec<MappingRule>, Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"projectId": project_id};
+            let mut cursor = collection.find(filter, None).await?;
+
+            let mut mapping_rules: Vec<MappingRule> = Vec::new();
+            while let Some(mapping_rule) = cursor.try_next().await? {
+                mapping_rules.push(mapping_rule);
+            }
+
+            Ok(mapping_rules)
+        }
+        Store::File(dir) => {
+            let mapping_rules: Vec<MappingRule> = file::read_all(dir, MAPPINGRULES_COL)?;
+
+            Ok(mapping_rules
+                .into_iter()
+                .filter(|mapping_rule| mapping_rule.project_id == project_id)
+                .collect())
+        }
+    }
+}
+
+pub async fn read_one(store: &Store, id: &str) -> Result<Option<MappingRule>, Box<dyn Error>> {
+    let oid = ObjectId::from_str(id)?;
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+
+            match collection.find_one(filter, None).await {
+                Ok(result) => Ok(result),
+                Err(e) => Err(Box::new(e)),
+            }
+        }
+        Store::File(dir) => {
+            let mapping_rules: Vec<MappingRule> = file::read_all(dir, MAPPINGRULES_COL)?;
+
+            Ok(mapping_rules.into_iter().find(|r| r.id == Some(oid)))
+        }
+    }
+}
+
+pub async fn create(store: &Store, mapping_rule: MappingRule) -> Result<String, Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let result = collection.insert_one(mapping_rule, None).await?;
+
+            match result.inserted_id.as_object_id() {
+                Some(id) => Ok(id.to_hex()),
+                None => Err(Box::new(MongoError::FailedToParseObjectId)),
+            }
+        }
+        Store::File(dir) => {
+            let id = mapping_rule.id.unwrap_or_default();
+            let new_mapping_rule = MappingRule {
+                id: Some(id),
+                ..mapping_rule
+            };
+
+            file::append(dir, MAPPINGRULES_COL, &[new_mapping_rule])?;
 
-    let filter = doc! {"projectId": project_id};
-    let mut cursor = collection.find(filter, None).await?;
+            Ok(id.to_hex())
+        }
+    }
+}
+
+pub async fn update(
+    store: &Store,
+    id: &str,
+    mapping_rule: MappingRule,
+) -> Result<String, Box<dyn Error>> {
+    let oid = ObjectId::from_str(id)?;
+    let new_mapping_rule = MappingRule {
+        id: Some(oid),
+        ..mapping_rule
+    };
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+
+            collection
+                .replace_one(filter, new_mapping_rule, None)
+                .await?;
+        }
+        Store::File(dir) => {
+            let mut mapping_rules: Vec<MappingRule> = file::read_all(dir, MAPPINGRULES_COL)?;
+            for r in mapping_rules.iter_mut().filter(|r| r.id == Some(oid)) {
+                *r = new_mapping_rule.clone();
+            }
 
-    let mut mapping_rules: Vec<MappingRule> = Vec::new();
-    while let Some(mapping_rule) = cursor.try_next().await? {
-        mapping_rules.push(mapping_rule);
+            file::write_all(dir, MAPPINGRULES_COL, &mapping_rules)?;
+        }
     }
 
-    Ok(mapping_rules)
+    Ok(String::from(id))
+}
+
+/// Deletes a mapping rule and returns whether it existed.
+pub async fn delete(store: &Store, id: &str) -> Result<bool, Box<dyn Error>> {
+    let oid = ObjectId::from_str(id)?;
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+            let result = collection.delete_one(filter, None).await?;
+
+            Ok(result.deleted_count > 0)
+        }
+        Store::File(dir) => {
+            let mut mapping_rules: Vec<MappingRule> = file::read_all(dir, MAPPINGRULES_COL)?;
+            let len = mapping_rules.len();
+            mapping_rules.retain(|r| r.id != Some(oid));
+
+            file::write_all(dir, MAPPINGRULES_COL, &mapping_rules)?;
+
+            Ok(mapping_rules.len() < len)
+        }
+    }
 }
 
 async fn get_col(url: &str) -> Result<Collection<MappingRule>, Box<dyn Error>> {
@@ -1,4 +1,7 @@
 pub mod drs;
+pub mod file;
+pub mod identity_policies;
 pub mod mapping_rules;
 pub mod mongo;
 pub mod projects;
+pub mod store;
@@ -1,7 +1,11 @@
-use super::mongo::{get_default_db, get_mongo_client, MongoError};
+use super::{
+    file,
+    mongo::{get_default_db, get_mongo_client, MongoError},
+    store::Store,
+};
 use futures::stream::TryStreamExt;
 use mongodb::{
-    bson::{doc, oid::ObjectId, DateTime},
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
     Collection,
 };
 use serde::{Deserialize, Serialize};
@@ -15,60 +19,194 @@ pub struct Project {
     pub id: Option<ObjectId>,
     pub name: String,
     pub created_at: DateTime,
-}
 
-pub async fn create(url: &str, name: String) -> Result<String, Box<dyn Error>> {
-    let collection = get_col(url).await?;
+    /// Connector types that mapping rules of the project may have. Any type is allowed if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connector_types: Vec<String>,
 
-    let new_project = Project {
-        id: None,
-        name,
-        created_at: DateTime::now(),
-    };
+    /// Patterns of internal and external code, which are used to extract dependency relations
+    #[serde(default, skip_serializing_if = "SourceFilter::is_empty")]
+    pub source_filter: SourceFilter,
+}
 
-    let result = collection.insert_one(new_project, None).await?;
+/// Patterns of packages or directories. A pattern is a glob, e.g., "edu.kaist.*" or
+/// "common/**", or a regular expression after "re:". A pattern after "!" excludes names.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceFilter {
+    /// Patterns of the sources of dependency relations, which are the code of the project
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub internal: Vec<String>,
+
+    /// Patterns of the targets of dependency relations. Any code that is not internal is external
+    /// if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external: Vec<String>,
+}
 
-    match result.inserted_id.as_object_id() {
-        Some(id) => Ok(id.to_hex()),
-        None => Err(Box::new(MongoError::FailedToParseObjectId)),
+impl SourceFilter {
+    pub fn is_empty(&self) -> bool {
+        self.internal.is_empty() && self.external.is_empty()
     }
 }
 
-pub async fn read_many(url: &str) -> Result<Vec<Project>, Box<dyn Error>> {
-    let collection = get_col(url).await?;
+pub async fn create(store: &Store, name: String) -> Result<String, Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let new_project = Project {
+                id: None,
+                name,
+                created_at: DateTime::now(),
+                connector_types: Vec::new(),
+                source_filter: SourceFilter::default(),
+            };
+
+            let result = collection.insert_one(new_project, None).await?;
+
+            match result.inserted_id.as_object_id() {
+                Some(id) => Ok(id.to_hex()),
+                None => Err(Box::new(MongoError::FailedToParseObjectId)),
+            }
+        }
+        Store::File(dir) => {
+            let id = ObjectId::new();
+            let new_project = Project {
+                id: Some(id),
+                name,
+                created_at: DateTime::now(),
+                connector_types: Vec::new(),
+                source_filter: SourceFilter::default(),
+            };
+
+            file::append(dir, PROJECTS_COL, &[new_project])?;
+
+            Ok(id.to_hex())
+        }
+    }
+}
 
-    let mut cursor = collection.find(None, None).await?;
+pub async fn read_many(store: &Store) -> Result<Vec<Project>, Box<dyn Error>> {
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
 
-    let mut projects: Vec<Project> = Vec::new();
-    while let Some(project) = cursor.try_next().await? {
-        projects.push(project);
-    }
+            let mut cursor = collection.find(None, None).await?;
 
-    Ok(projects)
+            let mut projects: Vec<Project> = Vec::new();
+            while let Some(project) = cursor.try_next().await? {
+                projects.push(project);
+            }
+
+            Ok(projects)
+        }
+        Store::File(dir) => file::read_all(dir, PROJECTS_COL),
+    }
 }
 
-pub async fn read_one(url: &str, id: &str) -> Result<Option<Project>, Box<dyn Error>> {
-    let collection = get_col(url).await?;
+pub async fn read_one(store: &Store, id: &str) -> Result<Option<Project>, Box<dyn Error>> {
+    let oid = ObjectId::from_str(id)?;
+
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+
+            match collection.find_one(filter, None).await {
+                Ok(result) => Ok(result),
+                Err(e) => Err(Box::new(e)),
+            }
+        }
+        Store::File(dir) => {
+            let projects: Vec<Project> = file::read_all(dir, PROJECTS_COL)?;
 
+            Ok(projects.into_iter().find(|p| p.id == Some(oid)))
+        }
+    }
+}
+
+pub async fn update(store: &Store, id: &str, name: String) -> Result<String, Box<dyn Error>> {
     let oid = ObjectId::from_str(id)?;
-    let filter = doc! {"_id": oid};
 
-    match collection.find_one(filter, None).await {
-        Ok(result) => Ok(result),
-        Err(e) => Err(Box::new(e)),
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+            let update = doc! {"$set": {"name": name}};
+
+            collection.update_one(filter, update, None).await?;
+        }
+        Store::File(dir) => {
+            let mut projects: Vec<Project> = file::read_all(dir, PROJECTS_COL)?;
+            for project in projects.iter_mut().filter(|p| p.id == Some(oid)) {
+                project.name = name.clone();
+            }
+
+            file::write_all(dir, PROJECTS_COL, &projects)?;
+        }
     }
+
+    Ok(String::from(id))
 }
 
-pub async fn update(url: &str, id: &str, name: String) -> Result<String, Box<dyn Error>> {
-    let collection = get_col(url).await?;
+pub async fn set_connector_types(
+    store: &Store,
+    id: &str,
+    connector_types: Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    let oid = ObjectId::from_str(id)?;
 
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
+
+            let filter = doc! {"_id": oid};
+            let update = doc! {"$set": {"connector_types": &connector_types}};
+
+            collection.update_one(filter, update, None).await?;
+        }
+        Store::File(dir) => {
+            let mut projects: Vec<Project> = file::read_all(dir, PROJECTS_COL)?;
+            for project in projects.iter_mut().filter(|p| p.id == Some(oid)) {
+                project.connector_types = connector_types.clone();
+            }
+
+            file::write_all(dir, PROJECTS_COL, &projects)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn set_source_filter(
+    store: &Store,
+    id: &str,
+    source_filter: SourceFilter,
+) -> Result<(), Box<dyn Error>> {
     let oid = ObjectId::from_str(id)?;
-    let filter = doc! {"_id": oid};
-    let update = doc! {"$set": {"name": name}};
 
-    collection.update_one(filter, update, None).await?;
+    match store {
+        Store::Mongo(url) => {
+            let collection = get_col(url).await?;
 
-    Ok(String::from(id))
+            let filter = doc! {"_id": oid};
+            let update = doc! {"$set": {"source_filter": to_bson(&source_filter)?}};
+
+            collection.update_one(filter, update, None).await?;
+        }
+        Store::File(dir) => {
+            let mut projects: Vec<Project> = file::read_all(dir, PROJECTS_COL)?;
+            for project in projects.iter_mut().filter(|p| p.id == Some(oid)) {
+                project.source_filter = source_filter.clone();
+            }
+
+            file::write_all(dir, PROJECTS_COL, &projects)?;
+        }
+    }
+
+    Ok(())
 }
 
 async fn get_col(url: &str) -> Result<Collection<Project>, Box<dyn Error>> {
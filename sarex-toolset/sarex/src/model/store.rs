@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use crate::config::{self, Config, Storage};
+
+/// A storage backend that keeps projects, dependency relations, and mapping rules.
+pub enum Store {
+    /// A MongoDB database, given by its URL
+    Mongo(String),
+
+    /// A local directory that keeps each collection as a JSON lines file
+    File(PathBuf),
+}
+
+impl Store {
+    pub fn from_config(config: &Config) -> Self {
+        match config.storage {
+            Storage::Mongo => Store::Mongo(config.db_url.clone()),
+            Storage::File => Store::File(match &config.data_dir {
+                Some(dir) => PathBuf::from(dir),
+                None => config::get_default_data_dir(),
+            }),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        match self {
+            Store::Mongo(url) => !url.is_empty(),
+            Store::File(_) => true,
+        }
+    }
+}
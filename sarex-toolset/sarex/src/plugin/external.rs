@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::model::drs::Dr;
+
+use super::{dir, parse_dr_records, DrExtractor, DrParams};
+
+const MANIFEST_FILE: &str = "plugin.json";
+
+#[derive(Debug)]
+enum PluginError {
+    CommandError(String, std::io::Error),
+    CommandFailed(String, String),
+}
+
+impl Error for PluginError {}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginError::CommandError(name, e) => write!(f, "Command error ({}): {}", name, e),
+            PluginError::CommandFailed(name, stderr) => {
+                write!(f, "Plugin {} failed: {}", name, stderr)
+            }
+        }
+    }
+}
+
+/// A manifest of an installed DR extractor, which is located at
+/// `$HOME/.sarex/plugins/<name>/plugin.json`.
+///
+/// `{root_path}` and `{sources}` in `args` and `env` are replaced with the arguments of `sarex dr`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PluginManifest {
+    pub name: String,
+    pub command: String,
+
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    pub languages: Vec<String>,
+}
+
+pub struct ExternalExtractor {
+    plugin_dir: PathBuf,
+    manifest: PluginManifest,
+}
+
+impl DrExtractor for ExternalExtractor {
+    fn read_drs(&self, project_id: &str, params: &DrParams) -> Result<Vec<Dr>, Box<dyn Error>> {
+        let mut command = Command::new(self.get_program());
+        command.current_dir(&self.plugin_dir);
+
+        for arg in &self.manifest.args {
+            command.arg(fill_params(arg, params));
+        }
+        for (key, value) in &self.manifest.env {
+            command.env(key, fill_params(value, params));
+        }
+
+        let output = match command.output() {
+            Ok(o) => o,
+            Err(e) => {
+                return Err(Box::new(PluginError::CommandError(
+                    self.manifest.name.clone(),
+                    e,
+                )))
+            }
+        };
+
+        if !output.status.success() {
+            return Err(Box::new(PluginError::CommandFailed(
+                self.manifest.name.clone(),
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )));
+        }
+
+        Ok(parse_dr_records(project_id, &output.stdout))
+    }
+}
+
+impl ExternalExtractor {
+    // A command with a path separator is relative to the plugin directory. Otherwise, it is
+    // looked up in $PATH, e.g., "java" or "node".
+    fn get_program(&self) -> PathBuf {
+        let command = Path::new(&self.manifest.command);
+        if command.is_absolute() || command.components().count() == 1 {
+            command.to_path_buf()
+        } else {
+            self.plugin_dir.join(command)
+        }
+    }
+}
+
+/// Finds an installed plugin that handles the language. A plugin is matched by its name or by one
+/// of the languages declared in its manifest.
+pub fn find(lang: &str) -> Result<Option<ExternalExtractor>, Box<dyn Error>> {
+    let plugin_dir = dir::get_plugin_dir();
+    if !plugin_dir.is_dir() {
+        return Ok(None);
+    }
+
+    let mut entries = fs::read_dir(&plugin_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        let manifest = match read_manifest(&path.join(MANIFEST_FILE)) {
+            Ok(m) => m,
+            Err(e) => {
+                warn!(
+                    "Skipped a malformed plugin manifest in {}: {}",
+                    path.display(),
+                    e
+                );
+                continue;
+            }
+        };
+
+        if manifest.name == lang || manifest.languages.iter().any(|l| l == lang) {
+            return Ok(Some(ExternalExtractor {
+                plugin_dir: path,
+                manifest,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn read_manifest(p: &Path) -> Result<PluginManifest, Box<dyn Error>> {
+    let content = fs::read_to_string(p)?;
+    let manifest = serde_json::from_str(&content)?;
+
+    Ok(manifest)
+}
+
+fn fill_params(template: &str, params: &DrParams) -> String {
+    template
+        .replace("{root_path}", params.root_path)
+        .replace("{sources}", params.sources)
+}
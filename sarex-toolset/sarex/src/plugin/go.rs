@@ -1,16 +1,15 @@
 use std::{error::Error, fmt::Display, process::Command};
 
-use serde::{Deserialize, Serialize};
-
 use crate::model::drs::Dr;
 
-use super::dir;
+use super::{dir, parse_dr_records, DrExtractor, DrParams};
 
 #[derive(Debug)]
 enum PluginError {
-    WrongArguments,
     NoGoFileInstalled,
+    NoMainPackage,
     CommandError(std::io::Error),
+    CommandFailed(String),
 }
 
 impl Error for PluginError {}
@@ -18,9 +17,12 @@ impl Error for PluginError {}
 impl Display for PluginError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            PluginError::WrongArguments => write!(f, "Wrong arguments"),
             PluginError::NoGoFileInstalled => write!(f, "No Go file installed"),
+            PluginError::NoMainPackage => {
+                write!(f, "No main package given. Give it with --sources")
+            }
             PluginError::CommandError(e) => write!(f, "Command error: {}", e),
+            PluginError::CommandFailed(stderr) => write!(f, "Command failed: {}", stderr),
         }
     }
 }
@@ -28,50 +30,35 @@ impl Display for PluginError {
 const PLUGIN_DIR: &str = "go";
 const GO_FILE: &str = "go-dependencies-reader";
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DrRecord {
-    caller: String,
-    callee: String,
-}
-
-pub fn read_drs(project_id: &str, params: Vec<&str>) -> Result<Vec<Dr>, Box<dyn Error>> {
-    if params.len() < 2 {
-        return Err(Box::new(PluginError::WrongArguments));
-    }
-
-    let root_path = params[0];
-    let pkg = params[1];
+pub struct GoExtractor;
 
-    let go_file = get_go_file()?;
-    let output = match Command::new(go_file)
-        .arg("-main")
-        .arg(pkg)
-        .arg("-dir")
-        .arg(root_path)
-        .output()
-    {
-        Ok(o) => o,
-        Err(e) => return Err(Box::new(PluginError::CommandError(e))),
-    };
-
-    let result = String::from_utf8_lossy(&output.stdout);
+impl DrExtractor for GoExtractor {
+    fn read_drs(&self, project_id: &str, params: &DrParams) -> Result<Vec<Dr>, Box<dyn Error>> {
+        // The reader starts from the main package, which is given as the sources.
+        if params.sources.is_empty() {
+            return Err(Box::new(PluginError::NoMainPackage));
+        }
 
-    let mut drs = Vec::new();
-    for line in result.lines() {
-        let record: DrRecord = match serde_json::from_str(line) {
-            Ok(d) => d,
-            Err(_) => continue,
+        let go_file = get_go_file()?;
+        let output = match Command::new(go_file)
+            .arg("-main")
+            .arg(params.sources)
+            .arg("-dir")
+            .arg(params.root_path)
+            .output()
+        {
+            Ok(o) => o,
+            Err(e) => return Err(Box::new(PluginError::CommandError(e))),
         };
 
-        drs.push(Dr {
-            id: None,
-            source: record.caller,
-            target: record.callee,
-            project_id: String::from(project_id),
-        });
-    }
+        if !output.status.success() {
+            return Err(Box::new(PluginError::CommandFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )));
+        }
 
-    Ok(drs)
+        Ok(parse_dr_records(project_id, &output.stdout))
+    }
 }
 
 fn get_go_file() -> Result<String, PluginError> {
@@ -1,13 +1,10 @@
-use serde::{Deserialize, Serialize};
-
 use crate::model::drs::Dr;
 use std::{error::Error, fmt::Display, process::Command};
 
-use super::dir;
+use super::{dir, parse_dr_records, DrExtractor, DrParams};
 
 #[derive(Debug)]
 enum PluginError {
-    WrongArguments,
     NoJavaDependencyReaderInstalled,
 }
 
@@ -16,7 +13,6 @@ impl Error for PluginError {}
 impl Display for PluginError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            PluginError::WrongArguments => write!(f, "Wrong arguments"),
             PluginError::NoJavaDependencyReaderInstalled => {
                 write!(f, "No Java dependency reader installed")
             }
@@ -27,43 +23,21 @@ impl Display for PluginError {
 const PLUGIN_DIR: &str = "java";
 const JAR_FILE: &str = "JavaDependenciesReader.jar";
 
-#[derive(Serialize, Deserialize, Debug)]
-struct DrRecord {
-    caller: String,
-    callee: String,
-}
-
-pub fn read_drs(project_id: &str, params: Vec<&str>) -> Result<Vec<Dr>, Box<dyn Error>> {
-    if params.is_empty() {
-        return Err(Box::new(PluginError::WrongArguments));
-    }
-
-    let jar_file = get_jar_file()?;
+pub struct JavaExtractor;
 
-    // java -jar JavaDependenciesReader.jar /Users/byron1st/Workspace/research/target_systems/bss/bin
-    let output = Command::new("java")
-        .arg("-jar")
-        .arg(jar_file)
-        .arg(params[0])
-        .output()?;
-    let result = String::from_utf8_lossy(&output.stdout);
+impl DrExtractor for JavaExtractor {
+    fn read_drs(&self, project_id: &str, params: &DrParams) -> Result<Vec<Dr>, Box<dyn Error>> {
+        let jar_file = get_jar_file()?;
 
-    let mut drs = Vec::new();
-    for line in result.lines() {
-        let record: DrRecord = match serde_json::from_str(line) {
-            Ok(d) => d,
-            Err(_) => continue,
-        };
+        // java -jar JavaDependenciesReader.jar /Users/byron1st/Workspace/research/target_systems/bss/bin
+        let output = Command::new("java")
+            .arg("-jar")
+            .arg(jar_file)
+            .arg(params.root_path)
+            .output()?;
 
-        drs.push(Dr {
-            id: None,
-            source: record.caller,
-            target: record.callee,
-            project_id: String::from(project_id),
-        });
+        Ok(parse_dr_records(project_id, &output.stdout))
     }
-
-    Ok(drs)
 }
 
 fn get_jar_file() -> Result<String, PluginError> {
@@ -1,25 +1,86 @@
-use crate::model::drs::Dr;
-use std::error::Error;
+use crate::model::drs::{Dr, DrDetails};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt::Display};
 
 mod dir;
+mod external;
 mod go;
 mod java;
 mod js;
 
-pub enum PluginKind {
-    Java,
-    Go,
-    JavaScript,
+/// Parameters given to a DR extractor by `sarex dr`.
+pub struct DrParams<'a> {
+    pub root_path: &'a str,
+    pub sources: &'a str,
 }
 
-pub fn read_drs(
-    project_id: &str,
-    kind: PluginKind,
-    params: Vec<&str>,
-) -> Result<Vec<Dr>, Box<dyn Error>> {
-    match kind {
-        PluginKind::Java => java::read_drs(project_id, params),
-        PluginKind::Go => go::read_drs(project_id, params),
-        PluginKind::JavaScript => js::read_drs(project_id, params),
+/// A program that extracts dependency relations from the source code of the target software.
+pub trait DrExtractor {
+    fn read_drs(&self, project_id: &str, params: &DrParams) -> Result<Vec<Dr>, Box<dyn Error>>;
+}
+
+#[derive(Debug)]
+enum PluginError {
+    NoSuchExtractor(String),
+}
+
+impl Error for PluginError {}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginError::NoSuchExtractor(lang) => {
+                write!(f, "No DR extractor installed for the language: {}", lang)
+            }
+        }
+    }
+}
+
+/// Finds a DR extractor for the language. Installed plugins with a manifest take precedence over
+/// the built-in extractors, so that a built-in one can be replaced without rebuilding sarex.
+pub fn get_extractor(lang: &str) -> Result<Box<dyn DrExtractor>, Box<dyn Error>> {
+    if let Some(extractor) = external::find(lang)? {
+        return Ok(Box::new(extractor));
+    }
+
+    match lang {
+        "java" => Ok(Box::new(java::JavaExtractor)),
+        "go" => Ok(Box::new(go::GoExtractor)),
+        "js" => Ok(Box::new(js::JsExtractor)),
+        _ => Err(Box::new(PluginError::NoSuchExtractor(lang.to_string()))),
     }
 }
+
+/// A dependency relation printed by an extractor. Only `caller` and `callee` are required, e.g.,
+/// `{"caller": "...", "callee": "...", "file": "src/App.java", "line": 12, "kind": "virtual"}`.
+#[derive(Serialize, Deserialize, Debug)]
+struct DrRecord {
+    caller: String,
+    callee: String,
+
+    #[serde(flatten)]
+    details: DrDetails,
+}
+
+/// Parses the output of an extractor, which prints a `DrRecord` as JSON per line.
+fn parse_dr_records(project_id: &str, output: &[u8]) -> Vec<Dr> {
+    let result = String::from_utf8_lossy(output);
+
+    let mut drs = Vec::new();
+    for line in result.lines() {
+        let record: DrRecord = match serde_json::from_str(line) {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+
+        drs.push(Dr {
+            id: None,
+            source: record.caller,
+            target: record.callee,
+            project_id: String::from(project_id),
+            details: record.details,
+        });
+    }
+
+    drs
+}
@@ -0,0 +1,514 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::OpenOptions,
+    io::{BufReader, Write},
+    path::Path,
+};
+
+use bson::oid::ObjectId;
+
+use crate::model::{drs::Dr, mapping_rules::MappingRule};
+
+pub fn read_mapping_rules(file_path_str: &str) -> Result<Vec<MappingRule>, Box<dyn Error>> {
+    let file_path = Path::new(file_path_str);
+    let mapping_rules_file = OpenOptions::new().read(true).open(file_path)?;
+    let reader = BufReader::new(mapping_rules_file);
+    let mapping_rules: Vec<MappingRule> = serde_json::from_reader(reader)?;
+
+    Ok(mapping_rules)
+}
+
+pub fn write_mapping_rules(
+    mapping_rules: &[MappingRule],
+    output_file_path_str: &str,
+) -> Result<(), Box<dyn Error>> {
+    // IDs are hex strings as in `reconstruction/*/mappingrules.json`, not extended JSON.
+    let mut values: Vec<serde_json::Value> = Vec::new();
+    for mapping_rule in mapping_rules {
+        let mut value = serde_json::to_value(mapping_rule)?;
+        if let Some(id) = mapping_rule.id {
+            value["_id"] = serde_json::Value::String(id.to_hex());
+        }
+        values.push(value);
+    }
+
+    let p = Path::new(output_file_path_str);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(p)?;
+    file.write_all(serde_json::to_string_pretty(&values)?.as_bytes())?;
+    Ok(())
+}
+
+pub struct Import {
+    pub new_mapping_rules: Vec<MappingRule>,
+    pub conflicts: Vec<Conflict>,
+}
+
+pub struct Conflict {
+    pub mapping_rule: MappingRule,
+    pub existing_id: String,
+}
+
+/// Prepares mapping rules read from a file to be stored in the project.
+///
+/// Each mapping rule is moved to the project, and its relation is linked to the dependency relation
+/// of the project with the same source and target, if any. A mapping rule that has the same
+/// procedure and relation as an existing one (or an earlier one in the file), or whose ID is
+/// already taken, is reported as a conflict instead.
+pub fn prepare_import(
+    mapping_rules: Vec<MappingRule>,
+    existing_mapping_rules: &[MappingRule],
+    drs: &[Dr],
+    project_id: &str,
+    new_ids: bool,
+) -> Import {
+    let mut new_mapping_rules: Vec<MappingRule> = Vec::new();
+    let mut conflicts: Vec<Conflict> = Vec::new();
+
+    for mut mapping_rule in mapping_rules {
+        mapping_rule.id = if new_ids {
+            Some(ObjectId::new())
+        } else {
+            mapping_rule.id.or_else(|| Some(ObjectId::new()))
+        };
+        mapping_rule.project_id = project_id.to_string();
+        mapping_rule.relation.project_id = project_id.to_string();
+
+        if let Some(dr) = drs.iter().find(|dr| {
+            dr.source == mapping_rule.relation.source && dr.target == mapping_rule.relation.target
+        }) {
+            if let Some(id) = dr.id {
+                mapping_rule.relation.id = id.to_hex();
+            }
+        }
+
+        let conflict = existing_mapping_rules
+            .iter()
+            .chain(new_mapping_rules.iter())
+            .find(|r| r.is_same_mapping(&mapping_rule) || r.id == mapping_rule.id);
+
+        match conflict {
+            Some(r) => conflicts.push(Conflict {
+                existing_id: r.id.map(|id| id.to_hex()).unwrap_or_default(),
+                mapping_rule,
+            }),
+            None => new_mapping_rules.push(mapping_rule),
+        }
+    }
+
+    Import {
+        new_mapping_rules,
+        conflicts,
+    }
+}
+
+#[derive(Debug)]
+pub enum RuleError {
+    LintFailed(usize),
+}
+
+impl Error for RuleError {}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            RuleError::LintFailed(count) => write!(f, "{} mapping rule errors", count),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+pub struct LintIssue {
+    pub severity: Severity,
+    pub mapping_rule_id: String,
+    pub message: String,
+}
+
+/// Checks mapping rules against the dependency relations of the project, each other, and the
+/// connector types of the project, if any. Errors are mistakes that make a mapping rule produce
+/// no or wrong CIs, and warnings are unusual but possibly intended.
+pub fn lint(
+    mapping_rules: &[MappingRule],
+    drs: &[Dr],
+    connector_types: &[String],
+) -> Vec<LintIssue> {
+    let mut issues: Vec<LintIssue> = Vec::new();
+
+    for (i, mapping_rule) in mapping_rules.iter().enumerate() {
+        let id = mapping_rule.id.map(|id| id.to_hex()).unwrap_or_default();
+        let mut add = |severity: Severity, message: String| {
+            issues.push(LintIssue {
+                severity,
+                mapping_rule_id: id.clone(),
+                message,
+            })
+        };
+
+        if mapping_rule.connector_type.is_empty() {
+            add(Severity::Error, "The connector type is empty".to_string());
+        } else if !connector_types.is_empty()
+            && !connector_types.contains(&mapping_rule.connector_type)
+        {
+            add(
+                Severity::Error,
+                format!(
+                    "The connector type {} is not one of the connector types of the project: {}",
+                    mapping_rule.connector_type,
+                    connector_types.join(", ")
+                ),
+            );
+        }
+
+        if mapping_rule.target_component_identifier_schema.is_empty() {
+            add(
+                Severity::Error,
+                "The target identifier schema is empty, so no CI is created".to_string(),
+            );
+        }
+        if mapping_rule.source_component_identifier_schema.is_empty() {
+            add(
+                Severity::Error,
+                "The source identifier schema is empty, so the source component is not identified"
+                    .to_string(),
+            );
+        }
+        for (name, schema) in [
+            ("source", &mapping_rule.source_component_identifier_schema),
+            ("target", &mapping_rule.target_component_identifier_schema),
+        ] {
+            for (j, identifier) in schema.iter().enumerate() {
+                if identifier.trim().is_empty() {
+                    add(
+                        Severity::Error,
+                        format!("The {} identifier schema has an empty identifier", name),
+                    );
+                } else if schema[..j].contains(identifier) {
+                    add(
+                        Severity::Warning,
+                        format!(
+                            "The {} identifier schema has {} more than once",
+                            name, identifier
+                        ),
+                    );
+                }
+            }
+        }
+
+        if mapping_rule.procedure != mapping_rule.relation.source {
+            add(
+                Severity::Error,
+                format!(
+                    "The procedure {} differs from the source of the dependency relation {}",
+                    mapping_rule.procedure, mapping_rule.relation.source
+                ),
+            );
+        }
+
+        let dr = drs
+            .iter()
+            .find(|dr| dr.id.map(|id| id.to_hex()) == Some(mapping_rule.relation.id.clone()));
+        match dr {
+            Some(dr)
+                if dr.source == mapping_rule.relation.source
+                    && dr.target == mapping_rule.relation.target => {}
+            Some(_) => add(
+                Severity::Error,
+                format!(
+                    "The dependency relation {} has a different source or target",
+                    mapping_rule.relation.id
+                ),
+            ),
+            None => match drs.iter().find(|dr| {
+                dr.source == mapping_rule.relation.source
+                    && dr.target == mapping_rule.relation.target
+            }) {
+                Some(dr) => add(
+                    Severity::Warning,
+                    format!(
+                        "The dependency relation is stored with another ID {}",
+                        dr.id.map(|id| id.to_hex()).unwrap_or_default()
+                    ),
+                ),
+                None => add(
+                    Severity::Error,
+                    format!(
+                        "The dependency relation {} -> {} is not stored in the project",
+                        mapping_rule.relation.source, mapping_rule.relation.target
+                    ),
+                ),
+            },
+        }
+
+        if let Some(duplicate) = mapping_rules[..i]
+            .iter()
+            .find(|r| r.is_same_mapping(mapping_rule))
+        {
+            add(
+                Severity::Error,
+                format!(
+                    "It maps the same procedure and dependency relation as {}",
+                    duplicate.id.map(|id| id.to_hex()).unwrap_or_default()
+                ),
+            );
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{drs::DrDetails, mapping_rules::Relation};
+
+    fn get_dr(source: &str, target: &str) -> Dr {
+        Dr {
+            id: Some(ObjectId::new()),
+            source: source.to_string(),
+            target: target.to_string(),
+            project_id: "p".to_string(),
+            details: DrDetails::default(),
+        }
+    }
+
+    fn get_mapping_rule(dr: &Dr) -> MappingRule {
+        MappingRule {
+            id: Some(ObjectId::new()),
+            project_id: "p".to_string(),
+            procedure: dr.source.clone(),
+            relation: Relation {
+                id: dr.id.map(|id| id.to_hex()).unwrap_or_default(),
+                source: dr.source.clone(),
+                target: dr.target.clone(),
+                project_id: "p".to_string(),
+            },
+            connector_type: "File".to_string(),
+            source_component_identifier_schema: vec!["process_id".to_string()],
+            target_component_identifier_schema: vec!["file_path".to_string()],
+        }
+    }
+
+    fn get_issues(
+        mapping_rules: &[MappingRule],
+        drs: &[Dr],
+        connector_types: &[String],
+    ) -> Vec<(Severity, String)> {
+        lint(mapping_rules, drs, connector_types)
+            .into_iter()
+            .map(|issue| (issue.severity, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn lint_accepts_a_valid_mapping_rule() {
+        let dr = get_dr("main.write", "os.WriteFile");
+        let mapping_rule = get_mapping_rule(&dr);
+
+        assert!(get_issues(&[mapping_rule], &[dr], &["File".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn lint_reports_errors() {
+        let dr = get_dr("main.write", "os.WriteFile");
+        let mut mapping_rule = get_mapping_rule(&dr);
+        mapping_rule.procedure = "main.main".to_string();
+        mapping_rule.connector_type = "Network".to_string();
+        mapping_rule.source_component_identifier_schema.clear();
+        mapping_rule.target_component_identifier_schema = vec![" ".to_string()];
+        let duplicate = mapping_rule.clone();
+
+        let issues = get_issues(&[mapping_rule, duplicate], &[dr], &["File".to_string()]);
+        let errors = issues
+            .iter()
+            .filter(|(severity, _)| *severity == Severity::Error)
+            .map(|(_, message)| message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), issues.len());
+        for message in [
+            "The connector type Network is not one of the connector types of the project: File",
+            "The source identifier schema is empty, so the source component is not identified",
+            "The target identifier schema has an empty identifier",
+            "The procedure main.main differs from the source of the dependency relation main.write",
+        ] {
+            assert_eq!(errors.iter().filter(|m| **m == message).count(), 2);
+        }
+        assert_eq!(
+            errors
+                .iter()
+                .filter(|m| m.starts_with("It maps the same procedure and dependency relation"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn lint_checks_the_dependency_relation() {
+        let dr = get_dr("main.write", "os.WriteFile");
+        let other = get_dr("main.read", "os.ReadFile");
+
+        let mut moved = get_mapping_rule(&dr);
+        moved.relation.id = ObjectId::new().to_hex();
+        let mut changed = get_mapping_rule(&dr);
+        changed.relation.id = other.id.unwrap().to_hex();
+        let mut missing = get_mapping_rule(&get_dr("main.remove", "os.Remove"));
+        missing.relation.id = String::new();
+
+        // Each separately, since the first two map the same relation.
+        let drs = [dr.clone(), other.clone()];
+        let issues = [moved, changed, missing]
+            .into_iter()
+            .flat_map(|mapping_rule| get_issues(&[mapping_rule], &drs, &[]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    Severity::Warning,
+                    format!(
+                        "The dependency relation is stored with another ID {}",
+                        dr.id.unwrap().to_hex()
+                    )
+                ),
+                (
+                    Severity::Error,
+                    format!(
+                        "The dependency relation {} has a different source or target",
+                        other.id.unwrap().to_hex()
+                    )
+                ),
+                (
+                    Severity::Error,
+                    "The dependency relation main.remove -> os.Remove is not stored in the project"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_warns_about_a_repeated_identifier() {
+        let dr = get_dr("main.write", "os.WriteFile");
+        let mut mapping_rule = get_mapping_rule(&dr);
+        mapping_rule
+            .source_component_identifier_schema
+            .push("process_id".to_string());
+
+        assert_eq!(
+            get_issues(&[mapping_rule], &[dr], &[]),
+            vec![(
+                Severity::Warning,
+                "The source identifier schema has process_id more than once".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn prepare_import_links_relations_and_moves_to_the_project() {
+        let dr = get_dr("main.write", "os.WriteFile");
+        let mut mapping_rule = get_mapping_rule(&dr);
+        mapping_rule.project_id = "other".to_string();
+        mapping_rule.relation.project_id = "other".to_string();
+        mapping_rule.relation.id = ObjectId::new().to_hex();
+        let id = mapping_rule.id;
+
+        let import = prepare_import(
+            vec![mapping_rule],
+            &[],
+            std::slice::from_ref(&dr),
+            "p",
+            false,
+        );
+
+        assert!(import.conflicts.is_empty());
+        let [imported] = import.new_mapping_rules.as_slice() else {
+            panic!("expected one mapping rule");
+        };
+        assert_eq!(imported.id, id);
+        assert_eq!(imported.project_id, "p");
+        assert_eq!(imported.relation.project_id, "p");
+        assert_eq!(imported.relation.id, dr.id.unwrap().to_hex());
+    }
+
+    #[test]
+    fn prepare_import_reports_conflicts() {
+        let dr = get_dr("main.write", "os.WriteFile");
+        let existing = get_mapping_rule(&dr);
+        let mut same_id = get_mapping_rule(&get_dr("main.read", "os.ReadFile"));
+        same_id.id = existing.id;
+        let mut same_mapping = get_mapping_rule(&dr);
+        same_mapping.connector_type = "Network".to_string();
+        let new = get_mapping_rule(&get_dr("main.remove", "os.Remove"));
+        let mut repeated = new.clone();
+        repeated.id = Some(ObjectId::new());
+
+        let import = prepare_import(
+            vec![same_id, same_mapping, new.clone(), repeated],
+            std::slice::from_ref(&existing),
+            &[],
+            "p",
+            false,
+        );
+
+        let existing_id = existing.id.unwrap().to_hex();
+        assert_eq!(
+            import
+                .conflicts
+                .iter()
+                .map(|c| c.existing_id.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                existing_id.as_str(),
+                existing_id.as_str(),
+                new.id.unwrap().to_hex().as_str()
+            ]
+        );
+        assert_eq!(
+            import
+                .new_mapping_rules
+                .iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>(),
+            vec![new.id]
+        );
+    }
+
+    #[test]
+    fn prepare_import_with_new_ids_avoids_id_conflicts() {
+        let existing = get_mapping_rule(&get_dr("main.write", "os.WriteFile"));
+        let mut mapping_rule = get_mapping_rule(&get_dr("main.read", "os.ReadFile"));
+        mapping_rule.id = existing.id;
+
+        let import = prepare_import(
+            vec![mapping_rule],
+            std::slice::from_ref(&existing),
+            &[],
+            "p",
+            true,
+        );
+
+        assert!(import.conflicts.is_empty());
+        assert_eq!(import.new_mapping_rules.len(), 1);
+        assert_ne!(import.new_mapping_rules[0].id, existing.id);
+        assert!(import.new_mapping_rules[0].id.is_some());
+    }
+}